@@ -1,23 +1,44 @@
-use color_eyre::Result;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::OpenOptions,
+    io::{IsTerminal, Write},
+    os::fd::OwnedFd,
+    path::PathBuf,
+    sync::mpsc::Receiver,
+    time::Duration,
+};
+
+use color_eyre::{Result, eyre::eyre};
 use ratatui::{
     Terminal,
     buffer::Buffer,
     crossterm::event::{self, Event, KeyCode},
     layout::{Constraint, Flex, Layout, Rect},
     prelude::CrosstermBackend,
-    style::{Color, Modifier, Style, Stylize},
+    style::Style,
     text::{Line, Span, Text},
     widgets::{
-        Block, BorderType, HighlightSpacing, List, ListItem, ListState, Paragraph, StatefulWidget,
-        Widget, Wrap,
+        Block, Borders, Clear, HighlightSpacing, List, ListItem, ListState, Padding, Paragraph, StatefulWidget,
+        Tabs, Widget, Wrap,
     },
 };
 use ratatui_image::{Resize, StatefulImage, picker::Picker};
 
 use crate::{
-    applications::{self, Action, Application, get_app_icon, spawn_app},
-    config::{Config, load_config},
+    applications::{self, Action, Application, MAIN_CATEGORIES, printed_command, spawn_app},
+    autostart,
+    cli::PrintFormat,
+    config::{Config, ConfigWarning, DetailsPosition, load_config, watched_files},
+    daemon,
+    emoji,
+    entries::{self, EntryForm},
+    files,
     image::get_image,
+    instance,
+    matching,
+    mime,
+    mode::Mode,
+    pins, session, watch,
 };
 
 pub struct App {
@@ -27,13 +48,77 @@ pub struct App {
     original_list: Vec<Application>,
     action_list: ActionList,
     config: Config,
+    mode: Mode,
+    /// Text printed to stdout once the terminal is restored
+    output: Option<String>,
+    /// Character sent to the emoji command once the terminal is restored
+    picked: Option<String>,
+    /// Waiting for the user to confirm the selected action
+    confirming: bool,
+    /// Problems found in the config, shown until a key is pressed
+    warnings: Vec<ConfigWarning>,
+    /// Title and text of the outcome of an action that keeps the launcher open, like an error,
+    /// shown until a key is pressed
+    message: Option<(&'static str, String)>,
+    config_path: Option<String>,
+    /// Sends a message when the config file or a theme file is saved
+    config_changes: Option<Receiver<PathBuf>>,
+    /// Desktop files created, changed or removed while the launcher is open
+    app_changes: Option<Receiver<PathBuf>>,
+    /// Desktop entry open in the editor
+    editor: Option<EntryForm>,
+    /// Icon paths already looked up, by icon name
+    icons: HashMap<String, Option<PathBuf>>,
+    /// Icon name and details of the selected file, as (path, icon, details). Finding its MIME type reads the MIME database
+    file_details: Option<(String, String, Vec<String>)>,
+    control: Option<Receiver<instance::Message>>,
+    /// Set when another launch asked this one to close
+    quitting: bool,
+    /// Files or URLs passed to the launched application
+    targets: Vec<String>,
+    /// Entries still being found in the background
+    index: Option<Receiver<Vec<Application>>>,
+    /// Print the command instead of running it
+    print: Option<PrintFormat>,
+    /// Only entries in this category are listed
+    category: Option<String>,
+    /// Category tabs as (category, label). Empty if the mode doesn't use them
+    category_tabs: Vec<(&'static str, &'static str)>,
+    /// Selected tab, 0 is "All"
+    selected_tab: usize,
+    /// IDs of the pinned apps, as written in the config or the state file
+    pinned: Vec<String>,
+}
+
+/// Startup options given on the command line
+#[derive(Default)]
+pub struct Options {
+    pub config_path: Option<String>,
+    pub mode: Mode,
+    /// Files or URLs passed to the launched application
+    pub targets: Vec<String>,
+    pub print: Option<PrintFormat>,
+    /// Initial search text
+    pub query: Option<String>,
+    /// Only list entries in this category
+    pub category: Option<String>,
+    /// Messages from later launches, see `instance`
+    pub control: Option<Receiver<instance::Message>>,
 }
 
 struct ApplicationList {
     applications: Vec<Application>,
+    /// Matched character positions of each application, to highlight them
+    highlights: Vec<Highlight>,
     state: ListState,
 }
 
+#[derive(Default)]
+struct Highlight {
+    name: Vec<usize>,
+    comment: Vec<usize>,
+}
+
 struct ActionList {
     actions: Vec<Action>,
     state: ListState,
@@ -41,52 +126,119 @@ struct ActionList {
 
 impl Widget for &mut App {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let [header_area, main_area, footer_area] = Layout::vertical([
+        let layout = self.config.layout.clone();
+        let compact = layout.compact
+            || area.width < layout.compact_width
+            || area.height < layout.compact_height;
+
+        let tabs_height = if self.category_tabs.is_empty() { 0 } else { 1 };
+        let help = self.help_lines(area.width.saturating_sub(2));
+        let footer_height = if layout.controls && !compact { help.len() as u16 + 2 } else { 0 };
+        let [header_area, tabs_area, main_area, footer_area] = Layout::vertical([
             Constraint::Length(3),
+            Constraint::Length(tabs_height),
             Constraint::Min(1),
-            Constraint::Length(3),
+            Constraint::Length(footer_height),
         ])
         .areas(area);
 
-        let [list_area, item_area] =
-            Layout::horizontal([Constraint::Percentage(80), Constraint::Fill(1)]).areas(main_area);
+        // Hidden panes get no space, and the list takes everything if there are no details
+        let has_details = !compact && (layout.icon || layout.info || layout.actions);
+        let details_constraints = [
+            Constraint::Percentage(if has_details { layout.list_ratio } else { 100 }),
+            Constraint::Fill(if has_details { 1 } else { 0 }),
+        ];
+        let [list_area, item_area] = match layout.details {
+            DetailsPosition::Right => Layout::horizontal(details_constraints).areas(main_area),
+            DetailsPosition::Bottom => Layout::vertical(details_constraints).areas(main_area),
+        };
 
-        let [icon_area, about_area, action_area] = Layout::vertical([
-            Constraint::Percentage(25),
-            Constraint::Fill(1),
-            Constraint::Fill(1),
-        ])
-        .areas(item_area);
+        let pane = |shown: bool, constraint: Constraint| {
+            if has_details && shown { constraint } else { Constraint::Length(0) }
+        };
+        let panes_constraints = [
+            pane(layout.icon, Constraint::Percentage(layout.icon_ratio)),
+            pane(layout.info, Constraint::Fill(1)),
+            pane(layout.actions, Constraint::Fill(1)),
+        ];
+        let [icon_area, about_area, action_area] = match layout.details {
+            DetailsPosition::Right => Layout::vertical(panes_constraints).areas(item_area),
+            DetailsPosition::Bottom => Layout::horizontal(panes_constraints).areas(item_area),
+        };
         self.render_header(header_area, buf);
-        self.render_list(list_area, buf);
+        self.render_tabs(tabs_area, buf);
+        self.render_list(list_area, compact, buf);
         self.render_selected_item(icon_area, about_area, action_area, buf);
-        self.render_footer(footer_area, buf);
+        if !footer_area.is_empty() {
+            self.render_footer(help, footer_area, buf);
+        }
+        if self.confirming {
+            self.render_confirm(area, buf);
+        }
+        if let Some(editor) = &self.editor {
+            self.render_editor(editor, area, buf);
+        }
+        if !self.warnings.is_empty() {
+            self.render_warnings(area, buf);
+        }
+        if let Some((title, text)) = &self.message {
+            self.render_message(title, text, area, buf);
+        }
     }
 }
 
 impl App {
-    pub fn new(config_path: Option<String>) -> Self {
-        let apps = applications::get_apps();
+    pub fn new(options: Options) -> Self {
+        let Options {
+            config_path,
+            mode,
+            targets,
+            print,
+            query,
+            category,
+            control,
+        } = options;
+        // Problems in the config are shown over the launcher, which starts with the defaults
+        let (config, warnings) = load_config(config_path.clone());
+        let config_changes = watch::spawn_watcher(watched_files(config_path.clone()));
+        let app_changes = if mode == Mode::Apps {
+            watch::spawn_watcher(applications::entry_files())
+        } else {
+            None
+        };
+        let mut apps = mode.items(&config, &targets);
+        if let Some(category) = &category {
+            apps.retain(|app| in_category(app, category));
+        }
         // Get the actions for the first application
         let info = if let Some(i) = apps.first() {
             i.clone()
         } else {
             Application {
-                entry: "".to_string(),
                 name: "No applications".to_string(),
                 comment: "No applications found".to_string(),
-                icon: "".to_string(),
-                terminal: false,
-                actions: vec![],
-                categories: vec![],
+                ..Default::default()
             }
         };
-        App {
+        let index = mode.index(&config);
+        // Only the categories that have something in them get a tab
+        let category_tabs = if mode == Mode::Apps {
+            MAIN_CATEGORIES
+                .into_iter()
+                .filter(|(category, _)| apps.iter().any(|app| in_category(app, category)))
+                .collect()
+        } else {
+            vec![]
+        };
+        let pinned = if mode == Mode::Apps { pins::load(&config) } else { vec![] };
+        let mut app = App {
             input: String::new(),
             character_index: 0,
             original_list: apps.clone(),
             application_list: ApplicationList {
-                applications: apps,
+                // The list can't be empty, keep the placeholder until there are entries
+                highlights: (0..apps.len().max(1)).map(|_| Highlight::default()).collect(),
+                applications: if apps.is_empty() { vec![info.clone()] } else { apps },
                 state: {
                     let mut state = ListState::default();
                     state.select(Some(0));
@@ -101,10 +253,39 @@ impl App {
                     state
                 },
             },
-            config: load_config(config_path).unwrap_or_else(|_| {
-                panic!("Failed to load config");
-            }),
+            config,
+            mode,
+            output: None,
+            picked: None,
+            confirming: false,
+            warnings,
+            message: None,
+            config_path,
+            config_changes,
+            app_changes,
+            editor: None,
+            icons: HashMap::new(),
+            file_details: None,
+            control,
+            quitting: false,
+            targets,
+            index,
+            print,
+            category,
+            category_tabs,
+            selected_tab: 0,
+            pinned,
+        };
+        match query {
+            Some(query) => {
+                app.character_index = query.chars().count();
+                app.update_input(query);
+            }
+            // Put the pinned apps first
+            None if !app.pinned.is_empty() => app.on_input_change(),
+            None => {}
         }
+        app
     }
 
     fn move_cursor_left(&mut self) {
@@ -141,6 +322,9 @@ impl App {
     }
 
     fn select_next_action(&mut self) {
+        if self.action_list.actions.is_empty() {
+            return;
+        }
         let is_not_last_action =
             self.action_list.state.selected() != Some(self.action_list.actions.len() - 1);
         if is_not_last_action {
@@ -151,6 +335,9 @@ impl App {
     }
 
     fn select_previous_action(&mut self) {
+        if self.action_list.actions.is_empty() {
+            return;
+        }
         let is_not_first_action = self.action_list.state.selected() != Some(0);
         if is_not_first_action {
             self.action_list.state.select_previous();
@@ -247,63 +434,529 @@ impl App {
     }
 
     fn on_input_change(&mut self) {
-        // Filter the applications based on the input
-        let filtered_apps: Vec<Application> = self
+        let mut filtered: Vec<(Application, Highlight)> = self
             .original_list
             .iter()
-            .filter(|app| app.name.to_lowercase().contains(&self.input.to_lowercase()))
-            .cloned()
+            .filter_map(|app| Some((app.clone(), self.highlight(app)?)))
             .collect();
+        if self.input.is_empty() {
+            // The sort is stable, so both groups stay in alphabetical order
+            filtered.sort_by_key(|(app, _)| !pins::contains(&self.pinned, &app.entry));
+        }
+        let (filtered_apps, highlights): (Vec<Application>, Vec<Highlight>) =
+            filtered.into_iter().unzip();
         if filtered_apps.is_empty() {
             let temp_app = Application {
-                entry: "".to_string(),
                 name: "No results".to_string(),
                 comment: "No applications found".to_string(),
-                icon: "".to_string(),
-                terminal: false,
                 actions: vec![Action {
                     name: "Try typing something else".to_string(),
                     command: "Or exit the application".to_string(),
                 }],
-                categories: vec![],
+                ..Default::default()
             };
             self.application_list.applications = vec![temp_app];
+            self.application_list.highlights = vec![Highlight::default()];
             self.application_list.state.select(Some(0));
             self.update_actions();
 
             return;
         }
         self.application_list.applications = filtered_apps;
+        self.application_list.highlights = highlights;
         self.application_list.state.select(Some(0));
         self.update_actions();
     }
 
-    fn run_action(&self) -> Result<()> {
+    /// Matches an entry against the search and the selected tab, returning the characters to highlight
+    fn highlight(&self, app: &Application) -> Option<Highlight> {
+        if !self.in_selected_tab(app) {
+            return None;
+        }
+        // Filter the applications based on the input, by name or keywords
+        let query = self.input.as_str();
+        // Paths are long, so files are matched fuzzily
+        let fuzzy = self.mode == Mode::Files;
+        match matching::find(query, &app.name, fuzzy) {
+            Some(name) => Some(Highlight {
+                name,
+                comment: vec![],
+            }),
+            None => {
+                app.keywords
+                    .iter()
+                    .find_map(|k| matching::find(query, k, fuzzy))?;
+                // Keywords aren't shown, but files use the path as keyword and comment
+                Some(Highlight {
+                    name: vec![],
+                    comment: matching::find(query, &app.comment, fuzzy).unwrap_or_default(),
+                })
+            }
+        }
+    }
+
+    fn toggle_pin(&mut self) {
+        if self.mode != Mode::Apps {
+            return;
+        }
+        let selected_index = self.application_list.state.selected().unwrap_or(0);
+        let entry = self.application_list.applications[selected_index].entry.clone();
+        if entry.is_empty() {
+            return;
+        }
+        match pins::toggle(&entry, &self.config) {
+            Ok(_) => self.pinned = pins::load(&self.config),
+            Err(e) => {
+                self.message = Some(("Error", format!("Couldn't save the pins: {}", e)));
+                return;
+            }
+        }
+        self.refilter();
+    }
+
+    /// Opens the selected application's desktop file in the editor
+    fn open_editor(&mut self) {
+        if self.mode != Mode::Apps {
+            return;
+        }
+        let selected_index = self.application_list.state.selected().unwrap_or(0);
+        let app = &self.application_list.applications[selected_index];
+        // Custom entries live in the config, not in a desktop file
+        if app.entry.is_empty() || app.custom {
+            return;
+        }
+        match EntryForm::edit(&app.entry) {
+            Ok(form) => self.editor = Some(form),
+            Err(e) => self.message = Some(("Error", format!("Couldn't open {}: {}", app.entry, e))),
+        }
+    }
+
+    /// Starts the selected application with the session
+    fn add_to_autostart(&mut self) {
+        if self.mode != Mode::Apps {
+            return;
+        }
+        let selected_index = self.application_list.state.selected().unwrap_or(0);
+        let app = &self.application_list.applications[selected_index];
+        if app.entry.is_empty() || app.custom {
+            return;
+        }
+        self.message = Some(match autostart::add(&app.entry) {
+            Ok(()) => ("Autostart", format!("{} starts with the session now", app.name)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                ("Autostart", format!("{} is already in autostart", app.name))
+            }
+            Err(e) => ("Error", format!("Couldn't add {} to autostart: {}", app.name, e)),
+        });
+    }
+
+    fn open_new_entry(&mut self) {
+        if self.mode == Mode::Apps {
+            self.editor = Some(EntryForm::create());
+        }
+    }
+
+    fn handle_editor_key(&mut self, key: event::KeyEvent) {
+        let Some(editor) = &mut self.editor else {
+            return;
+        };
+        let control = key.modifiers.contains(event::KeyModifiers::CONTROL);
+        let result = match key.code {
+            KeyCode::Esc => {
+                self.editor = None;
+                return;
+            }
+            KeyCode::Up | KeyCode::BackTab => {
+                editor.selected = editor.selected.checked_sub(1).unwrap_or(entries::FIELDS.len() - 1);
+                return;
+            }
+            KeyCode::Down | KeyCode::Tab => {
+                editor.selected = (editor.selected + 1) % entries::FIELDS.len();
+                return;
+            }
+            KeyCode::Backspace => {
+                editor.value_mut().pop();
+                return;
+            }
+            KeyCode::Enter => editor.save(),
+            KeyCode::Char('s') if control => editor.save(),
+            KeyCode::Char('d') if control => match &editor.id {
+                Some(id) => entries::hide(id).map(|_| id.clone()).map_err(|e| e.to_string()),
+                None => return,
+            },
+            KeyCode::Char('x') if control => match &editor.id {
+                Some(id) => entries::reset(id).map(|_| id.clone()).map_err(|e| e.to_string()),
+                None => return,
+            },
+            KeyCode::Char(c) if !control => {
+                editor.value_mut().push(c);
+                return;
+            }
+            _ => return,
+        };
+        match result {
+            Ok(_) => {
+                self.editor = None;
+                self.reload_entries();
+            }
+            Err(e) => editor.error = Some(e),
+        }
+    }
+
+    /// Reads the entries again after a desktop file changed, keeping the search and the selection
+    fn reload_entries(&mut self) {
+        if self.mode == Mode::Apps {
+            daemon::reload(&self.config);
+        }
+        let mut apps = self.mode.items(&self.config, &self.targets);
+        if let Some(category) = &self.category {
+            apps.retain(|app| in_category(app, category));
+        }
+        self.original_list = apps;
+        self.refilter();
+    }
+
+    fn in_selected_tab(&self, app: &Application) -> bool {
+        match self.selected_tab.checked_sub(1) {
+            Some(i) => in_category(app, self.category_tabs[i].0),
+            None => true,
+        }
+    }
+
+    fn select_next_tab(&mut self) {
+        if self.category_tabs.is_empty() {
+            return;
+        }
+        self.selected_tab = (self.selected_tab + 1) % (self.category_tabs.len() + 1);
+        self.on_input_change();
+    }
+
+    fn select_previous_tab(&mut self) {
+        if self.category_tabs.is_empty() {
+            return;
+        }
+        self.selected_tab = if self.selected_tab == 0 {
+            self.category_tabs.len()
+        } else {
+            self.selected_tab - 1
+        };
+        self.on_input_change();
+    }
+
+    /// Adds the entries found in the background since the last call, keeping the selected one.
+    /// Returns whether there was something new
+    fn receive_index(&mut self) -> bool {
+        let Some(index) = &self.index else {
+            return false;
+        };
+        let batches: Vec<Vec<Application>> = index.try_iter().collect();
+        if batches.is_empty() {
+            return false;
+        }
+        // Only the new entries are matched, they go after the ones already listed like a full refilter would put them
+        let mut matched = vec![];
+        for app in batches.into_iter().flatten() {
+            if self.category.as_ref().is_some_and(|category| !in_category(&app, category)) {
+                continue;
+            }
+            if let Some(highlight) = self.highlight(&app) {
+                matched.push((app.clone(), highlight));
+            }
+            self.original_list.push(app);
+        }
+        if matched.is_empty() {
+            return true;
+        }
+        let list = &mut self.application_list;
+        // Replace the "No results" entry
+        let no_results = list.applications.iter().all(|app| app.entry.is_empty());
+        if no_results {
+            list.applications.clear();
+            list.highlights.clear();
+        }
+        for (app, highlight) in matched {
+            list.applications.push(app);
+            list.highlights.push(highlight);
+        }
+        if no_results {
+            list.state.select(Some(0));
+            self.update_actions();
+        }
+        true
+    }
+
+    /// Filters the list again, keeping the selected entry if it is still there
+    fn refilter(&mut self) {
+        let selected_index = self.application_list.state.selected().unwrap_or(0);
+        let selected_entry = self.application_list.applications[selected_index].entry.clone();
+        let selected_action = self.action_list.state.selected();
+        self.on_input_change();
+        if let Some(i) = self
+            .application_list
+            .applications
+            .iter()
+            .position(|app| !selected_entry.is_empty() && app.entry == selected_entry)
+        {
+            self.application_list.state.select(Some(i));
+            self.update_actions();
+            // The entry may have fewer actions now, if its desktop file changed
+            let last_action = self.action_list.actions.len().saturating_sub(1);
+            self.action_list.state.select(selected_action.map(|i| i.min(last_action)));
+        }
+    }
+
+    /// Loads the config again, keeping the list and the search as they are
+    fn reload_config(&mut self) {
+        let (config, warnings) = load_config(self.config_path.clone());
+        self.config = config;
+        self.warnings = warnings;
+        // The icon theme may have changed
+        self.icons.clear();
+        if self.mode == Mode::Apps {
+            self.pinned = pins::load(&self.config);
+        }
+        if self.mode == Mode::Files {
+            // Reading the files again would drop the ones found by the index
+            self.refilter();
+        } else {
+            // Overrides, custom entries, session commands and handlers come from the config
+            self.reload_entries();
+        }
+    }
+
+    /// Reloads the config if it was saved since the last check
+    fn receive_config_changes(&mut self) -> bool {
+        let Some(changes) = &self.config_changes else {
+            return false;
+        };
+        // Saving often writes the file more than once
+        let mut changed = false;
+        while changes.try_recv().is_ok() {
+            changed = true;
+        }
+        if changed {
+            self.reload_config();
+        }
+        changed
+    }
+
+    /// Reads the desktop files that changed since the last check, keeping the search and the selection
+    fn receive_app_changes(&mut self) -> bool {
+        let Some(changes) = &self.app_changes else {
+            return false;
+        };
+        // Installing a package often writes the same file more than once
+        let ids: BTreeSet<String> = changes
+            .try_iter()
+            .filter_map(|path| Some(path.file_name()?.to_string_lossy().to_string()))
+            .collect();
+        if ids.is_empty() {
+            return false;
+        }
+        for id in &ids {
+            applications::update_app(&mut self.original_list, id, false, &self.config);
+        }
+        if let Some(category) = &self.category {
+            self.original_list.retain(|app| in_category(app, category));
+        }
+        self.refilter();
+        true
+    }
+
+    /// Handles the messages sent by later launches
+    fn receive_control(&mut self) -> bool {
+        let Some(control) = &self.control else {
+            return false;
+        };
+        let messages: Vec<instance::Message> = control.try_iter().collect();
+        let received = !messages.is_empty();
+        for message in messages {
+            match message {
+                instance::Message::Quit => self.quitting = true,
+                instance::Message::Query(query) => {
+                    self.editor = None;
+                    self.confirming = false;
+                    self.character_index = query.chars().count();
+                    self.update_input(query);
+                }
+            }
+        }
+        received
+    }
+
+    /// Waits until there is a terminal event to read. Returns false instead if new entries arrived,
+    /// a desktop file or the config changed or another launch sent a message first, so the launcher can be redrawn
+    fn wait_for_event(&mut self) -> Result<bool> {
+        if self.index.is_none()
+            && self.config_changes.is_none()
+            && self.app_changes.is_none()
+            && self.control.is_none()
+        {
+            return Ok(true);
+        }
+        loop {
+            if event::poll(Duration::from_millis(100))? {
+                return Ok(true);
+            }
+            // All of them are checked, so none waits for the others
+            let received = self.receive_index();
+            let changed = self.receive_config_changes();
+            let updated = self.receive_app_changes();
+            if self.receive_control() || received || changed || updated {
+                return Ok(false);
+            }
+        }
+    }
+
+    /// Runs the selected action, showing what went wrong if it failed. Returns whether the launcher should close
+    fn run_selected(&mut self) -> bool {
+        match self.run_action() {
+            Ok(close) => close,
+            Err(e) => {
+                self.message = Some(("Error", e.to_string()));
+                false
+            }
+        }
+    }
+
+    /// Runs the selected action. Returns whether the launcher should close
+    fn run_action(&mut self) -> Result<bool> {
         let selected_index = self.application_list.state.selected().unwrap_or(0);
         let selected_action_index = self.action_list.state.selected().unwrap_or(0);
 
-        let selected_action =
-            &self.application_list.applications[selected_index].actions[selected_action_index];
+        // Placeholder entries like "No results" have nothing to run
+        if self.application_list.applications[selected_index].entry.is_empty() {
+            return Ok(true);
+        }
+
+        let Some(selected_action) =
+            self.application_list.applications[selected_index].actions.get(selected_action_index)
+        else {
+            // Nothing to run, the launcher stays open
+            return Ok(false);
+        };
         let command = selected_action.command.clone();
 
-        let is_terminal = self.application_list.applications[selected_index].terminal;
+        let app = self.application_list.applications[selected_index].clone();
 
-        spawn_app(command, is_terminal, &self.config)
+        match self.mode {
+            Mode::Apps | Mode::Ssh | Mode::Session | Mode::Open => {
+                let targets = self.targets.clone();
+                self.launch(command, &app, &targets)?;
+            }
+            Mode::Emoji => {
+                // The command runs once the terminal is restored, without one the character is printed
+                match &self.config.emoji.command {
+                    Some(_) => self.picked = Some(command),
+                    None => self.output = Some(command),
+                }
+            }
+            Mode::Files => {
+                let handler = mime::default_handler(&command, &self.config)?;
+                self.launch(handler.actions[0].command.clone(), &handler, &[command])?;
+            }
+            // Changing an entry keeps the launcher open, to see its new state
+            Mode::Autostart => match app.actions[selected_action_index].name.as_str() {
+                autostart::RUN_NOW => self.launch(command, &app, &[])?,
+                name => {
+                    let result = match name {
+                        autostart::ENABLE => autostart::set_enabled(&app.entry, true),
+                        autostart::DISABLE => autostart::set_enabled(&app.entry, false),
+                        _ => autostart::remove_user_copy(&app.entry),
+                    };
+                    result.map_err(|e| eyre!("Couldn't change {}: {}", app.name, e))?;
+                    self.reload_entries();
+                    return Ok(false);
+                }
+            },
+        }
+        Ok(true)
     }
 
-    fn run(mut self, terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>) -> Result<()> {
+    /// Spawns the command, or keeps it to be printed once the terminal is restored
+    fn launch(&mut self, command: String, app: &Application, targets: &[String]) -> Result<()> {
+        match self.print {
+            Some(format) => {
+                let args = printed_command(command, app, targets, &self.config);
+                self.output = Some(format.format(&args));
+                Ok(())
+            }
+            None => spawn_app(command, app, targets, &self.config),
+        }
+    }
+
+    fn needs_confirmation(&self) -> bool {
+        let selected_index = self.application_list.state.selected().unwrap_or(0);
+        let entry = &self.application_list.applications[selected_index].entry;
+        self.mode == Mode::Session && session::needs_confirmation(entry, &self.config)
+    }
+
+    fn run(&mut self, terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>) -> Result<()> {
         loop {
             // terminal.draw(|frame| self.draw(frame))?;
-            terminal.draw(|frame| frame.render_widget(&mut self, frame.area()))?;
+            terminal.draw(|frame| frame.render_widget(&mut *self, frame.area()))?;
+            let has_event = self.wait_for_event()?;
+            if self.quitting {
+                terminal.clear()?;
+                return Ok(());
+            }
+            if !has_event {
+                continue;
+            }
             if let Event::Key(key) = event::read()? {
+                if !self.warnings.is_empty() {
+                    self.warnings.clear();
+                    continue;
+                }
+                if self.message.take().is_some() {
+                    continue;
+                }
+                if self.editor.is_some() {
+                    self.handle_editor_key(key);
+                    continue;
+                }
+                if self.confirming {
+                    match key.code {
+                        KeyCode::Char('y') | KeyCode::Enter => {
+                            if self.run_selected() {
+                                terminal.clear()?;
+                                return Ok(());
+                            }
+                            self.confirming = false;
+                        }
+                        _ => self.confirming = false,
+                    }
+                    continue;
+                }
                 match key.code {
                     KeyCode::Char('q') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
                         terminal.clear()?;
                         return Ok(());
                     }
+                    KeyCode::Char('r') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                        self.reload_config()
+                    }
+                    KeyCode::Char('p') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                        self.toggle_pin()
+                    }
+                    KeyCode::Char('e') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                        self.open_editor()
+                    }
+                    KeyCode::Char('n') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                        self.open_new_entry()
+                    }
+                    KeyCode::Char('a') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                        self.add_to_autostart()
+                    }
                     KeyCode::Delete if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
                         self.delete_word()
                     }
+                    KeyCode::Left if key.modifiers.contains(event::KeyModifiers::ALT) => {
+                        self.select_previous_tab()
+                    }
+                    KeyCode::Right if key.modifiers.contains(event::KeyModifiers::ALT) => {
+                        self.select_next_tab()
+                    }
                     KeyCode::Left if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
                         self.move_cursor_word_left()
                     }
@@ -320,10 +973,13 @@ impl App {
                         terminal.clear()?;
                         return Ok(());
                     }
+                    KeyCode::Enter if self.needs_confirmation() => self.confirming = true,
                     KeyCode::Enter => {
-                        self.run_action()?;
-                        terminal.clear()?;
-                        return Ok(());
+                        let close = self.run_selected();
+                        if close {
+                            terminal.clear()?;
+                            return Ok(());
+                        }
                     }
                     KeyCode::Up => self.select_previous_app(),
                     KeyCode::Down => self.select_next_app(),
@@ -342,18 +998,22 @@ impl App {
         let [area] = Layout::horizontal([horizontal])
             .flex(Flex::Center)
             .areas(area);
-        return area;
+        area
+    }
+
+    /// Block with the configured border type. Without borders, the padding keeps the contents in place
+    fn block<'a>(&self, title: &'a str, style: Style) -> Block<'a> {
+        let block = Block::new().title(title).style(style);
+        match self.config.appearance.border_type {
+            Some(border_type) => block.borders(Borders::ALL).border_type(border_type),
+            None => block.padding(Padding::uniform(1)),
+        }
     }
 
     fn render_header(&self, header_area: Rect, buf: &mut Buffer) {
         Paragraph::new(self.input.as_str())
-            .fg(self.config.appearance.search_input)
-            .block(
-                Block::bordered()
-                    .title("Search")
-                    .fg(self.config.appearance.search_border)
-                    .border_type(BorderType::Rounded),
-            )
+            .style(self.config.appearance.search_input)
+            .block(self.block("Search", self.config.appearance.search_border))
             .render(header_area, buf);
         buf.set_string(
             header_area.x + self.character_index as u16 + 1,
@@ -363,48 +1023,194 @@ impl App {
                 .nth(self.character_index)
                 .unwrap_or(' ')
                 .to_string(),
-            Style::default()
-                .bg(self.config.appearance.search_input)
-                .fg(Color::White),
+            self.config.appearance.cursor,
         );
     }
 
-    fn render_footer(&self, footer_area: Rect, buf: &mut Buffer) {
-        Paragraph::new(
-            "↑↓ to navigate apps | Tab to navigate actions | Enter to run action | Esc to exit",
-        )
-        .fg(self.config.appearance.help_text)
-        .block(
-            Block::bordered()
-                .title("Controls")
-                .fg(self.config.appearance.help_border)
-                .border_type(BorderType::Rounded),
-        )
-        .render(footer_area, buf);
-    }
-
-    fn render_list(&mut self, area: Rect, buf: &mut Buffer) {
-        let block = Block::new()
-            .title("Applications")
-            .border_type(BorderType::Rounded)
-            .borders(ratatui::widgets::Borders::ALL)
-            .fg(self.config.appearance.applications_border);
+    fn render_confirm(&self, area: Rect, buf: &mut Buffer) {
+        let selected_index = self.application_list.state.selected().unwrap_or(0);
+        let name = &self.application_list.applications[selected_index].name;
+        let popup_area = Self::center(area, Constraint::Length(40), Constraint::Length(5));
+        Clear.render(popup_area, buf);
+        Paragraph::new(vec![
+            Line::from(Span::styled(
+                format!("{}?", name),
+                self.config.appearance.text,
+            )),
+            Line::from(Span::styled(
+                "y/Enter to confirm | any other key to cancel",
+                self.config.appearance.help_text,
+            )),
+        ])
+        .centered()
+        .block(self.block("Confirm", self.config.appearance.help_border))
+        .wrap(Wrap { trim: true })
+        .render(popup_area, buf);
+    }
+
+    fn render_warnings(&self, area: Rect, buf: &mut Buffer) {
+        let mut lines: Vec<Line> = self
+            .warnings
+            .iter()
+            .map(|warning| Line::from(Span::styled(warning.to_string(), self.config.appearance.text)))
+            .collect();
+        lines.push(Line::from(Span::styled(
+            "Using the defaults for these | any key to dismiss",
+            self.config.appearance.help_text,
+        )));
+        self.render_popup("Config problems", lines, area, buf);
+    }
+
+    fn render_message(&self, title: &str, text: &str, area: Rect, buf: &mut Buffer) {
+        let lines = vec![
+            Line::from(Span::styled(text.to_string(), self.config.appearance.text)),
+            Line::from(Span::styled("Any key to dismiss", self.config.appearance.help_text)),
+        ];
+        self.render_popup(title, lines, area, buf);
+    }
+
+    fn render_popup(&self, title: &str, lines: Vec<Line>, area: Rect, buf: &mut Buffer) {
+        let width = area.width.saturating_sub(4).min(80);
+        // Long lines are wrapped, the popup grows to fit them
+        let inner_width = width.saturating_sub(2).max(1) as usize;
+        let height: usize = lines.iter().map(|line| line.width().div_ceil(inner_width).max(1)).sum();
+        let popup_area = Self::center(area, Constraint::Length(width), Constraint::Length(height as u16 + 2));
+        Clear.render(popup_area, buf);
+        Paragraph::new(lines)
+            .block(self.block(title, self.config.appearance.error))
+            .wrap(Wrap { trim: false })
+            .render(popup_area, buf);
+    }
+
+    fn render_editor(&self, editor: &EntryForm, area: Rect, buf: &mut Buffer) {
+        let selected_style = self
+            .config
+            .appearance
+            .selected_app
+            .patch(self.config.appearance.selected_app_text);
+        let mut lines: Vec<Line> = entries::FIELDS
+            .iter()
+            .zip(&editor.values)
+            .enumerate()
+            .map(|(i, (key, value))| {
+                let mut spans = vec![
+                    Span::styled(format!("{:<12}", key), self.config.appearance.subtext),
+                    Span::styled(value.clone(), self.config.appearance.text),
+                ];
+                if i == editor.selected {
+                    spans.push(Span::styled(" ", self.config.appearance.cursor));
+                    Line::from(spans).style(selected_style)
+                } else {
+                    Line::from(spans)
+                }
+            })
+            .collect();
+        lines.push(Line::from(""));
+        if let Some(error) = &editor.error {
+            lines.push(Line::from(Span::styled(error.clone(), self.config.appearance.error)));
+        }
+        let help = match &editor.id {
+            Some(id) if entries::has_user_file(id) => {
+                "↑↓ to change field | Enter to save | Ctrl+D to hide | Ctrl+X to reset | Esc to cancel"
+            }
+            Some(_) => "↑↓ to change field | Enter to save | Ctrl+D to hide | Esc to cancel",
+            None => "↑↓ to change field | Enter to save | Esc to cancel",
+        };
+        lines.push(Line::from(Span::styled(help, self.config.appearance.help_text)));
+
+        let title = match &editor.id {
+            Some(id) => format!("Edit {}", id),
+            None => "New entry".to_string(),
+        };
+        let width = area.width.saturating_sub(4).min(80);
+        let popup_area = Self::center(area, Constraint::Length(width), Constraint::Length(lines.len() as u16 + 4));
+        Clear.render(popup_area, buf);
+        Paragraph::new(lines)
+            .block(self.block(&title, self.config.appearance.help_border))
+            .wrap(Wrap { trim: false })
+            .render(popup_area, buf);
+    }
+
+    fn render_tabs(&self, tabs_area: Rect, buf: &mut Buffer) {
+        if self.category_tabs.is_empty() {
+            return;
+        }
+        let titles = std::iter::once("All").chain(self.category_tabs.iter().map(|(_, label)| *label));
+        Tabs::new(titles)
+            .select(self.selected_tab)
+            .style(self.config.appearance.subtext)
+            .highlight_style(
+                self
+                    .config
+                    .appearance
+                    .selected_app
+                    .patch(self.config.appearance.selected_app_text),
+            )
+            .render(tabs_area, buf);
+    }
+
+    /// Keys of the mode, split into lines that fit in the width
+    fn help_lines(&self, width: u16) -> Vec<String> {
+        let mut keys = vec!["↑↓ to navigate", "Tab to navigate actions", "Enter to run action"];
+        if !self.category_tabs.is_empty() {
+            keys.push("Alt+←→ to change category");
+        }
+        if self.mode == Mode::Apps {
+            keys.extend(["Ctrl+P to pin", "Ctrl+E to edit", "Ctrl+N to add an entry", "Ctrl+A to autostart"]);
+        }
+        keys.extend(["Ctrl+R to reload the config", "Esc to exit"]);
+
+        let mut lines: Vec<String> = vec![];
+        for key in keys {
+            match lines.last_mut() {
+                Some(line) if line.chars().count() + 3 + key.chars().count() <= width as usize => {
+                    line.push_str(" | ");
+                    line.push_str(key);
+                }
+                _ => lines.push(key.to_string()),
+            }
+        }
+        lines
+    }
+
+    fn render_footer(&self, help: Vec<String>, footer_area: Rect, buf: &mut Buffer) {
+        Paragraph::new(help.into_iter().map(Line::from).collect::<Vec<Line>>())
+            .style(self.config.appearance.help_text)
+            .block(self.block("Controls", self.config.appearance.help_border))
+            .render(footer_area, buf);
+    }
+
+    fn render_list(&mut self, area: Rect, compact: bool, buf: &mut Buffer) {
+        let block = self.block(self.mode.title(), self.config.appearance.applications_border);
+        let highlight_style = self.config.appearance.match_highlight;
         let items: Vec<ListItem> = self
             .application_list
             .applications
             .iter()
-            .map(|app| {
+            .zip(&self.application_list.highlights)
+            .map(|(app, highlight)| {
+                let mut name = highlight_line(
+                    &app.name,
+                    &highlight.name,
+                    self.config.appearance.text,
+                    highlight_style,
+                );
+                if pins::contains(&self.pinned, &app.entry) {
+                    let marker = Span::styled("★ ", self.config.appearance.text);
+                    name.spans.insert(0, marker);
+                }
+                // The compact layout only shows the names
+                if compact {
+                    return ListItem::new(name);
+                }
                 let text = Text::from(vec![
-                    Line::from(Span::styled(
-                        app.name.clone(),
-                        Style::default()
-                            .fg(self.config.appearance.text)
-                            .add_modifier(Modifier::BOLD),
-                    )),
-                    Line::from(Span::styled(
-                        app.comment.clone(),
-                        Style::default().fg(self.config.appearance.subtext),
-                    )),
+                    name,
+                    highlight_line(
+                        &app.comment,
+                        &highlight.comment,
+                        self.config.appearance.subtext,
+                        highlight_style,
+                    ),
                 ]);
                 ListItem::new(text)
             })
@@ -412,69 +1218,103 @@ impl App {
         let final_list = List::new(items)
             .block(block)
             .highlight_style(
-                Style::default()
-                    .bg(self.config.appearance.selected_app)
-                    .fg(self.config.appearance.selected_app_text),
+                self
+                    .config
+                    .appearance
+                    .selected_app
+                    .patch(self.config.appearance.selected_app_text),
             )
             .highlight_symbol(">> ")
             .highlight_spacing(HighlightSpacing::WhenSelected);
         StatefulWidget::render(final_list, area, buf, &mut self.application_list.state);
     }
 
-    fn render_selected_item(
-        &mut self,
-        icon_area: Rect,
-        about_area: Rect,
-        action_area: Rect,
-        buf: &mut Buffer,
-    ) {
-        let info = if let Some(i) = self.application_list.state.selected() {
-            self.application_list.applications[i].clone()
-        } else {
-            self.application_list.applications[0].clone()
-        };
-
-        let icon_path = get_app_icon(info.icon, &self.config).unwrap_or_default();
+    fn render_icon(&mut self, info: &Application, icon_area: Rect, buf: &mut Buffer) {
+        // Looked up once per icon, not on every redraw
+        let icon_path = self
+            .icons
+            .entry(info.icon.clone())
+            .or_insert_with(|| daemon::app_icon(info.icon.clone(), &self.config))
+            .clone()
+            .unwrap_or_default();
         if icon_path.to_str().unwrap().is_empty() {
             let text = Text::from(vec![Line::from(Span::styled(
                 "No icon available",
-                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                self.config.appearance.error,
             ))]);
-            let no_icon = Paragraph::new(text).block(
-                Block::bordered()
-                    .title("Icon")
-                    .fg(self.config.appearance.icon_border)
-                    .border_type(BorderType::Rounded),
-            );
+            let no_icon = Paragraph::new(text).block(self.block("Icon", self.config.appearance.icon_border));
             no_icon.render(icon_area, buf);
         } else {
             let mut picker = Picker::from_query_stdio().unwrap();
             picker.set_background_color([0, 0, 0, 0]);
             let dyn_img = get_image(icon_path.clone());
             let mut img = picker.new_resize_protocol(dyn_img.unwrap());
-            Block::new()
-                .title("Icon")
-                .border_type(BorderType::Rounded)
-                .borders(ratatui::widgets::Borders::ALL)
-                .fg(self.config.appearance.icon_border)
-                .render(icon_area, buf);
+            let block = self.block("Icon", self.config.appearance.icon_border);
+            let inner = block.inner(Self::center(icon_area, Constraint::Percentage(90), Constraint::Percentage(90)));
+            block.render(icon_area, buf);
             StatefulWidget::render(
                 StatefulImage::new().resize(Resize::Scale(None)),
-                Block::new()
-                    .title("Icon")
-                    .border_type(BorderType::Rounded)
-                    .borders(ratatui::widgets::Borders::ALL)
-                    .inner(Self::center(icon_area, Constraint::Percentage(90), Constraint::Percentage(90))),
+                inner,
                 buf,
                 &mut img,
             );
         }
+    }
+
+    fn render_glyph(&self, info: &Application, icon_area: Rect, buf: &mut Buffer) {
+        let text = Text::from(vec![
+            Line::from(Span::styled(
+                info.icon.clone(),
+                self.config.appearance.text,
+            )),
+            Line::from(Span::styled(
+                info.comment.clone(),
+                self.config.appearance.subtext,
+            )),
+        ]);
+        let block = self.block("Glyph", self.config.appearance.icon_border);
+        let inner = block.inner(icon_area);
+        block.render(icon_area, buf);
+        Paragraph::new(text)
+            .centered()
+            .render(Self::center(inner, Constraint::Fill(1), Constraint::Length(2)), buf);
+    }
+
+    fn render_selected_item(
+        &mut self,
+        icon_area: Rect,
+        about_area: Rect,
+        action_area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let mut info = if let Some(i) = self.application_list.state.selected() {
+            self.application_list.applications[i].clone()
+        } else {
+            self.application_list.applications[0].clone()
+        };
+        if self.mode == Mode::Files && !info.entry.is_empty() {
+            if self.file_details.as_ref().is_none_or(|(path, _, _)| *path != info.entry) {
+                let details = (info.entry.clone(), files::icon_name(&info.entry), files::metadata_lines(&info.entry));
+                self.file_details = Some(details);
+            }
+            if let Some((_, icon, details)) = &self.file_details {
+                info.icon = icon.clone();
+                info.categories = details.clone();
+            }
+        }
+
+        // Looking up and decoding the icon is slow, so skip it if the pane is hidden
+        if !icon_area.is_empty() {
+            if self.mode == Mode::Emoji {
+                self.render_glyph(&info, icon_area, buf);
+            } else {
+                self.render_icon(&info, icon_area, buf);
+            }
+        }
 
         let name_line = Line::from(Span::styled(
             info.name.clone(),
-            Style::default()
-                .fg(self.config.appearance.text)
-                .add_modifier(Modifier::BOLD),
+            self.config.appearance.text,
         ));
         let comment_line = Line::from(Span::styled(
             if info.comment.is_empty() {
@@ -482,35 +1322,29 @@ impl App {
             } else {
                 info.comment.as_str()
             },
-            Style::default().fg(self.config.appearance.subtext),
-        ));
-        let categories_line = Line::from(Span::styled(
-            info.categories.join(", "),
-            Style::default().fg(self.config.appearance.subtext),
+            self.config.appearance.subtext,
         ));
+        let categories_lines = if matches!(self.mode, Mode::Files | Mode::Autostart) {
+            // Files and autostart entries show one line per detail
+            info.categories.clone()
+        } else {
+            vec![info.categories.join(", ")]
+        };
 
-        let text = vec![
-            name_line,
-            Line::from("\n"),
-            comment_line,
-            Line::from("\n"),
-            categories_line,
-        ];
+        let mut text = vec![name_line, Line::from("\n"), comment_line, Line::from("\n")];
+        text.extend(categories_lines.into_iter().map(|line| {
+            Line::from(Span::styled(line, self.config.appearance.subtext))
+        }));
+        if info.custom {
+            text.push(Line::from(Span::styled("Custom entry from the config", self.config.appearance.subtext)));
+        }
 
         let selected_item = Paragraph::new(text)
-            .block(
-                Block::bordered()
-                    .title("Info")
-                    .fg(self.config.appearance.info_border)
-                    .border_type(BorderType::Rounded),
-            )
+            .block(self.block("Info", self.config.appearance.info_border))
             .wrap(Wrap { trim: true });
         selected_item.render(about_area, buf);
 
-        let block = Block::new()
-            .title("Actions")
-            .border_type(BorderType::Rounded)
-            .borders(ratatui::widgets::Borders::ALL);
+        let block = self.block("Actions", self.config.appearance.actions_border);
         let items: Vec<ListItem> = self
             .action_list
             .actions
@@ -519,13 +1353,11 @@ impl App {
                 let text = Text::from(vec![
                     Line::from(Span::styled(
                         action.name.clone(),
-                        Style::default()
-                            .fg(self.config.appearance.text)
-                            .add_modifier(Modifier::BOLD),
+                        self.config.appearance.text,
                     )),
                     Line::from(Span::styled(
                         action.command.clone(),
-                        Style::default().fg(self.config.appearance.subtext),
+                        self.config.appearance.subtext,
                     )),
                 ]);
                 ListItem::new(text)
@@ -533,22 +1365,71 @@ impl App {
             .collect();
         let final_list = List::new(items)
             .block(block)
-            .fg(self.config.appearance.actions_border)
-            .highlight_style(
-                Style::default()
-                    .bg(self.config.appearance.selected_app)
-                    .fg(Color::Black),
-            )
+            .style(self.config.appearance.actions_border)
+            .highlight_style(self.config.appearance.selected_action)
             .highlight_symbol(">> ")
             .highlight_spacing(HighlightSpacing::WhenSelected);
         StatefulWidget::render(final_list, action_area, buf, &mut self.action_list.state);
     }
 }
 
-pub fn startup(config_path: Option<String>) -> Result<()> {
+/// Builds a line where the characters at the given positions use the highlight style
+fn highlight_line(text: &str, positions: &[usize], style: Style, highlight: Style) -> Line<'static> {
+    let mut spans: Vec<Span> = Vec::new();
+    let mut current = String::new();
+    let mut current_highlighted = false;
+    for (i, c) in text.chars().enumerate() {
+        let highlighted = positions.contains(&i);
+        if highlighted != current_highlighted && !current.is_empty() {
+            let style = if current_highlighted { style.patch(highlight) } else { style };
+            spans.push(Span::styled(std::mem::take(&mut current), style));
+        }
+        current_highlighted = highlighted;
+        current.push(c);
+    }
+    let style = if current_highlighted { style.patch(highlight) } else { style };
+    spans.push(Span::styled(current, style));
+    Line::from(spans)
+}
+
+fn in_category(app: &Application, category: &str) -> bool {
+    app.categories.iter().any(|c| c.eq_ignore_ascii_case(category))
+}
+
+/// Points stdout to the terminal while the launcher is drawn, so the printed command or character
+/// is the only thing written when stdout is a pipe, like in `$(termlaunch --print)`.
+/// Returns the original stdout, to put it back before printing
+fn redirect_stdout() -> Result<Option<OwnedFd>> {
+    if std::io::stdout().is_terminal() {
+        return Ok(None);
+    }
+    let saved = rustix::io::dup(std::io::stdout())?;
+    match OpenOptions::new().read(true).write(true).open("/dev/tty") {
+        Ok(tty) => rustix::stdio::dup2_stdout(&tty)?,
+        // Without a controlling terminal, stderr is the best bet
+        Err(_) => rustix::stdio::dup2_stdout(std::io::stderr())?,
+    }
+    Ok(Some(saved))
+}
+
+pub fn startup(options: Options) -> Result<()> {
     color_eyre::install()?;
+    let saved_stdout = redirect_stdout()?;
     let mut terminal: Terminal<CrosstermBackend<std::io::Stdout>> = ratatui::init();
-    let app_result = App::new(config_path).run(&mut terminal);
+    let mut app = App::new(options);
+    let app_result = app.run(&mut terminal);
+    // Dropping the terminal shows the cursor, which must go to the terminal too
+    drop(terminal);
     ratatui::restore();
+    if let Some(saved) = saved_stdout {
+        std::io::stdout().flush()?;
+        rustix::stdio::dup2_stdout(&saved)?;
+    }
+    if let Some(output) = app.output {
+        println!("{}", output);
+    }
+    if let (Some(glyph), Some(command)) = (&app.picked, &app.config.emoji.command) {
+        emoji::pick(glyph, command).map_err(|e| eyre!("Couldn't run the emoji command `{}`: {}", command, e))?;
+    }
     app_result
 }
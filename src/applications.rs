@@ -1,14 +1,20 @@
-use std::{os::unix::process::CommandExt, path::PathBuf, process::Stdio};
+use std::{
+    collections::BTreeMap,
+    os::unix::process::CommandExt,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
 
-use color_eyre::eyre::Error;
+use color_eyre::eyre::{Error, eyre};
 use freedesktop_file_parser::{EntryType, parse};
 use freedesktop_icons::lookup;
+use serde::{Deserialize, Serialize};
 
 use std::process::Command;
 
-use crate::config;
+use crate::{config, mime};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Application {
     pub entry: String,
     pub name: String,
@@ -16,16 +22,51 @@ pub struct Application {
     pub icon: String,
     pub terminal: bool,
     pub categories: Vec<String>,
+    /// Extra search terms, matched alongside the name
+    pub keywords: Vec<String>,
+    /// MIME types the application can open
+    pub mime_types: Vec<String>,
     pub actions: Vec<Action>,
+    /// Extra environment variables for the commands
+    pub env: BTreeMap<String, String>,
+    /// Directory the commands run in, the home directory if not set
+    pub working_dir: Option<String>,
+    /// Marked as NoDisplay or hidden by an override
+    pub hidden: bool,
+    /// Defined in the config instead of a desktop file
+    pub custom: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Action {
     pub name: String,
     pub command: String,
 }
 
-pub fn get_apps() -> Vec<Application> {
+/// Main categories from the freedesktop menu spec, with the label shown in the launcher
+pub const MAIN_CATEGORIES: [(&str, &str); 13] = [
+    ("AudioVideo", "Multimedia"),
+    ("Audio", "Audio"),
+    ("Video", "Video"),
+    ("Development", "Development"),
+    ("Education", "Education"),
+    ("Game", "Games"),
+    ("Graphics", "Graphics"),
+    ("Network", "Internet"),
+    ("Office", "Office"),
+    ("Science", "Science"),
+    ("Settings", "Settings"),
+    ("System", "System"),
+    ("Utility", "Utilities"),
+];
+
+pub fn get_apps(config: &config::Config) -> Vec<Application> {
+    load_apps(false, config)
+}
+
+/// Loads the desktop entries with the overrides from the config applied, optionally keeping
+/// the hidden ones. Those are not shown in the launcher but can still be used to open files
+pub fn load_apps(include_no_display: bool, config: &config::Config) -> Vec<Application> {
     // system entries, should be $XDG_DATA_DIRS/applications or /usr/local/share/applications:/usr/share/applications
     let system_entries = std::env::var("XDG_DATA_DIRS")
         .unwrap_or_else(|_| "/usr/local/share:/usr/share".to_string());
@@ -50,12 +91,22 @@ pub fn get_apps() -> Vec<Application> {
         get_desktop_entries(true, user_entries, &mut apps);
     }
 
+    apps.extend(config.custom_apps.iter().map(to_application));
+    apply_overrides(&mut apps, &config.overrides);
+    if !include_no_display {
+        apps.retain(|app| !app.hidden);
+    }
+
     // Order the applications by name, case insensitive
-    apps.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    apps.sort_by_key(|a| a.name.to_lowercase());
     apps
 }
 
-fn get_desktop_entries(is_user: bool, path: String, apps: &mut Vec<Application>) {
+fn get_desktop_entries(
+    is_user: bool,
+    path: String,
+    apps: &mut Vec<Application>,
+) {
     // Check the applications directory
     let app_dir = format!("{}/applications", path);
     if let Ok(entries) = std::fs::read_dir(app_dir) {
@@ -66,63 +117,217 @@ fn get_desktop_entries(is_user: bool, path: String, apps: &mut Vec<Application>)
                 .map(|s| s == "desktop")
                 .unwrap_or(false)
             {
-                // Get the text of the file
-                let file_content =
-                    std::fs::read_to_string(entry.path()).unwrap_or_else(|_| "".to_string());
-                // Parse the file
-                let parsed = parse(&file_content);
-
-                if parsed.is_err() {
+                let Some(app) = parse_entry(&entry.path()) else {
                     continue;
-                }
-                let parsed = parsed.unwrap();
-
-                if let EntryType::Application(app) = &parsed.entry.entry_type {
-                    if parsed.entry.no_display.unwrap_or(false) {
-                        continue;
-                    };
-                    let mut actions = Vec::new();
-
-                    if let Some(exec) = &app.exec {
-                        actions.push(Action {
-                            name: "Run".to_string(),
-                            command: exec.to_string(),
-                        });
-                    }
-
-                    for (_name, action) in parsed.actions {
-                        actions.push(Action {
-                            name: action.name.default,
-                            command: action.exec.unwrap(),
-                        });
-                    }
-
-                    let app = Application {
-                        entry: entry.file_name().to_str().unwrap_or("").to_string(),
-                        name: parsed.entry.name.default,
-                        icon: parsed.entry.icon.unwrap_or_default().content,
-                        terminal: app.terminal.unwrap_or(false),
-                        comment: parsed.entry.comment.unwrap_or_default().default,
-                        categories: app.categories.clone().unwrap_or_default(),
-                        actions,
-                    };
-                    // if the entry is user, first check if it already exists in the entries, if it does, replace it, if not, push it
-                    if is_user {
-                        if let Some(index) = apps.iter().position(|x| x.entry == app.entry) {
-                            apps[index] = app;
-                        } else {
-                            apps.push(app);
-                        }
+                };
+                // if the entry is user, first check if it already exists in the entries, if it does, replace it, if not, push it
+                if is_user {
+                    if let Some(index) = apps.iter().position(|x| x.entry == app.entry) {
+                        apps[index] = app;
                     } else {
-                        // if the entry is system, just push it
                         apps.push(app);
                     }
+                } else {
+                    // if the entry is system, just push it
+                    apps.push(app);
                 }
             }
         }
     }
 }
 
+/// Reads a desktop file, `None` if it can't be parsed or isn't an application
+fn parse_entry(path: &Path) -> Option<Application> {
+    // Get the text of the file
+    let file_content = std::fs::read_to_string(path).ok()?;
+    parse_desktop_entry(path.file_name()?.to_str()?, &file_content)
+}
+
+/// Parses the content of the desktop file with this ID, `None` if it isn't an application
+pub fn parse_desktop_entry(id: &str, file_content: &str) -> Option<Application> {
+    // Parse the file
+    let parsed = parse(file_content).ok()?;
+
+    let EntryType::Application(app) = &parsed.entry.entry_type else {
+        return None;
+    };
+    let mut actions = Vec::new();
+
+    if let Some(exec) = &app.exec {
+        actions.push(Action {
+            name: "Run".to_string(),
+            command: exec.to_string(),
+        });
+    }
+
+    for (_name, action) in parsed.actions {
+        actions.push(Action {
+            name: action.name.default,
+            command: action.exec.unwrap(),
+        });
+    }
+
+    Some(Application {
+        entry: id.to_string(),
+        name: parsed.entry.name.default,
+        icon: parsed.entry.icon.unwrap_or_default().content,
+        terminal: app.terminal.unwrap_or(false),
+        comment: parsed.entry.comment.unwrap_or_default().default,
+        categories: app.categories.clone().unwrap_or_default(),
+        keywords: vec![],
+        mime_types: app.mime_type.clone().unwrap_or_default(),
+        actions,
+        env: BTreeMap::new(),
+        working_dir: app.path.clone(),
+        // A user entry marked NoDisplay also hides the system one it replaces
+        hidden: parsed.entry.no_display.unwrap_or(false),
+        custom: false,
+    })
+}
+
+/// Patterns matching every desktop file read by `load_apps`, for the watcher
+pub fn entry_files() -> Vec<PathBuf> {
+    mime::data_dirs()
+        .into_iter()
+        .map(|dir| PathBuf::from(dir).join("applications").join("*.desktop"))
+        .collect()
+}
+
+/// Reads the desktop file with this ID again after it was created, changed or removed,
+/// updating a list loaded by `load_apps` in place. The list stays sorted by name
+pub fn update_app(apps: &mut Vec<Application>, id: &str, include_no_display: bool, config: &config::Config) {
+    apps.retain(|app| app.custom || app.entry != id);
+    // The user's entry replaces the system ones
+    let Some(mut app) = mime::data_dirs()
+        .into_iter()
+        .find_map(|dir| parse_entry(&Path::new(&dir).join("applications").join(id)))
+    else {
+        return;
+    };
+    apply_overrides(std::slice::from_mut(&mut app), &config.overrides);
+    if app.hidden && !include_no_display {
+        return;
+    }
+    let name = app.name.to_lowercase();
+    let index = apps.partition_point(|a| a.name.to_lowercase() <= name);
+    apps.insert(index, app);
+}
+
+fn to_application(custom: &config::CustomApp) -> Application {
+    let mut actions = Vec::new();
+    if let Some(command) = &custom.command {
+        actions.push(Action {
+            name: "Run".to_string(),
+            command: command.clone(),
+        });
+    }
+    for action in custom.actions.iter().flatten() {
+        actions.push(Action {
+            name: action.name.clone(),
+            command: action.command.clone(),
+        });
+    }
+    Application {
+        entry: custom.id.clone().unwrap_or_else(|| custom.name.clone()),
+        name: custom.name.clone(),
+        comment: custom.comment.clone().unwrap_or_default(),
+        icon: custom.icon.clone().unwrap_or_default(),
+        terminal: custom.terminal.unwrap_or(false),
+        categories: custom.categories.clone().unwrap_or_default(),
+        actions,
+        custom: true,
+        ..Default::default()
+    }
+}
+
+fn apply_overrides(apps: &mut [Application], overrides: &[config::AppOverride]) {
+    for o in overrides {
+        let id = o.id.strip_suffix(".desktop").unwrap_or(&o.id);
+        let Some(app) = apps
+            .iter_mut()
+            .find(|app| app.entry.strip_suffix(".desktop").unwrap_or(&app.entry) == id)
+        else {
+            continue;
+        };
+        if let Some(name) = &o.name {
+            app.name = name.clone();
+        }
+        if let Some(comment) = &o.comment {
+            app.comment = comment.clone();
+        }
+        if let Some(icon) = &o.icon {
+            app.icon = icon.clone();
+        }
+        if let Some(hidden) = o.hidden {
+            app.hidden = hidden;
+        }
+        if let Some(terminal) = o.terminal {
+            app.terminal = terminal;
+        }
+        if let Some(env) = &o.env {
+            app.env.extend(env.clone());
+        }
+        if let Some(cwd) = &o.cwd {
+            app.working_dir = Some(cwd.clone());
+        }
+        if let Some(exec) = &o.exec {
+            // The main command is the first action, entries without one get it added
+            match app.actions.first_mut() {
+                Some(action) if action.name == "Run" => action.command = exec.clone(),
+                _ => app.actions.insert(
+                    0,
+                    Action {
+                        name: "Run".to_string(),
+                        command: exec.clone(),
+                    },
+                ),
+            }
+        }
+        if let Some(prefix) = &o.prefix {
+            for action in &mut app.actions {
+                action.command = format!("{} {}", prefix, action.command);
+            }
+        }
+    }
+}
+
+/// Finds an application by its desktop file ID, with or without the `.desktop` suffix
+pub fn find_app<'a>(apps: &'a [Application], id: &str) -> Option<&'a Application> {
+    let id = id.strip_suffix(".desktop").unwrap_or(id);
+    apps.iter()
+        .find(|app| app.entry.strip_suffix(".desktop").unwrap_or(&app.entry) == id)
+}
+
+/// Applications matching the query, best matches first.
+/// An exact name beats a name starting with the query, which beats a word starting with it,
+/// then any match in the name and finally a match in the keywords
+pub fn search(apps: &[Application], query: &str) -> Vec<Application> {
+    let query = query.to_lowercase();
+    let mut ranked: Vec<(usize, &Application)> = apps
+        .iter()
+        .filter_map(|app| {
+            let name = app.name.to_lowercase();
+            let rank = if name == query {
+                0
+            } else if name.starts_with(&query) {
+                1
+            } else if name.split_whitespace().any(|word| word.starts_with(&query)) {
+                2
+            } else if name.contains(&query) {
+                3
+            } else if app.keywords.iter().any(|k| k.to_lowercase().contains(&query)) {
+                4
+            } else {
+                return None;
+            };
+            Some((rank, app))
+        })
+        .collect();
+    // The sort is stable, so matches with the same rank keep the alphabetical order
+    ranked.sort_by_key(|(rank, _)| *rank);
+    ranked.into_iter().map(|(_, app)| app.clone()).collect()
+}
+
 pub fn get_app_icon(name: String, config: &config::Config) -> Option<PathBuf> {
     lookup(name.as_str())
         .with_size(1024)
@@ -130,11 +335,21 @@ pub fn get_app_icon(name: String, config: &config::Config) -> Option<PathBuf> {
         .find()
 }
 
-pub fn spawn_app(command: String, terminal: bool, config: &config::Config) -> Result<(), Error> {
+/// Turns a desktop entry command into the program and arguments that would be run,
+/// wrapped in the terminal if needed. `targets` are the files or URLs that replace the
+/// %f/%F/%u/%U field codes, the other field codes are removed
+pub fn resolve_command(
+    command: String,
+    terminal: bool,
+    targets: &[String],
+    config: &config::Config,
+) -> Vec<String> {
     // Split the command into arguments, if it contains spaces, except if its in quotes
     let mut args = Vec::new();
     let mut current_arg = String::new();
     let mut in_quotes = false;
+    // An empty argument is kept if it was quoted, like `""`
+    let mut quoted = false;
     let mut skip_next = false;
     for c in command.chars() {
         if skip_next {
@@ -147,43 +362,69 @@ pub fn spawn_app(command: String, terminal: bool, config: &config::Config) -> Re
         }
         if c == '"' {
             in_quotes = !in_quotes;
+            quoted = true;
             continue;
         }
         if c == ' ' && !in_quotes {
             args.push(current_arg.clone());
             current_arg.clear();
+            quoted = false;
             continue;
         }
         current_arg.push(c);
     }
-    if !current_arg.is_empty() {
+    if !current_arg.is_empty() || quoted {
         args.push(current_arg);
     }
 
-    // Remove the arguments that starts with %
-    args.retain(|arg| !arg.starts_with('%'));
+    // Expand the field codes and remove the arguments that starts with %
+    let mut args: Vec<String> = args
+        .into_iter()
+        .flat_map(|arg| match arg.as_str() {
+            "%f" => targets.first().map(|t| to_path(t)).into_iter().collect(),
+            "%F" => targets.iter().map(|t| to_path(t)).collect(),
+            "%u" => targets.first().cloned().into_iter().collect(),
+            "%U" => targets.to_vec(),
+            _ if arg.starts_with('%') => vec![],
+            _ => vec![arg],
+        })
+        .collect();
+
+    if terminal {
+        args.insert(0, config.terminal.clone());
+        args.insert(1, "-e".to_string());
+    }
+    args
+}
+
+/// Runs a command of the application, in its working directory and with its environment variables
+pub fn spawn_app(
+    command: String,
+    app: &Application,
+    targets: &[String],
+    config: &config::Config,
+) -> Result<(), Error> {
+    let mut args = resolve_command(command, app.terminal, targets, config);
+    if args.is_empty() {
+        return Err(eyre!("{} has nothing to run", app.name));
+    }
 
     let program = args[0].clone();
     // remove the first argument
     args.remove(0);
 
-    let mut command_builder: Command;
-
-    command_builder = if terminal {
-        let mut cmd = Command::new(&config.terminal);
-        cmd.arg("-e").arg(program);
-        cmd
-    } else {
-        Command::new(program)
-    };
+    let mut command_builder = Command::new(program);
 
     for arg in args {
         command_builder.arg(arg);
     }
 
+    let working_dir = working_dir(app).unwrap_or_else(|| std::env::var("HOME").unwrap_or_else(|_| ".".to_string()));
+
     unsafe {
         command_builder
-            .current_dir(std::env::var("HOME").unwrap_or_else(|_| ".".to_string()))
+            .current_dir(working_dir)
+            .envs(&app.env)
             .stdin(Stdio::null())
             .stdout(Stdio::null())
             .stderr(Stdio::null())
@@ -197,3 +438,34 @@ pub fn spawn_app(command: String, terminal: bool, config: &config::Config) -> Re
 
     Ok(())
 }
+
+/// The command as `--print` shows it. Entries with environment variables or a working directory
+/// are run through `env`, so the printed command does the same as `spawn_app`
+pub fn printed_command(command: String, app: &Application, targets: &[String], config: &config::Config) -> Vec<String> {
+    let args = resolve_command(command, app.terminal, targets, config);
+    let working_dir = working_dir(app);
+    if app.env.is_empty() && working_dir.is_none() {
+        return args;
+    }
+    let mut printed = vec!["env".to_string()];
+    if let Some(dir) = working_dir {
+        printed.extend(["-C".to_string(), dir]);
+    }
+    printed.extend(app.env.iter().map(|(key, value)| format!("{}={}", key, value)));
+    printed.extend(args);
+    printed
+}
+
+/// Directory set by the entry or an override, with `~` expanded
+fn working_dir(app: &Application) -> Option<String> {
+    let dir = app.working_dir.as_deref()?;
+    Some(match dir.strip_prefix('~') {
+        Some(rest) => format!("{}{}", std::env::var("HOME").unwrap_or_default(), rest),
+        None => dir.to_string(),
+    })
+}
+
+/// %f and %F expect local paths, so file:// URLs are turned back into paths
+fn to_path(target: &str) -> String {
+    mime::file_url_path(target).unwrap_or_else(|| target.to_string())
+}
@@ -0,0 +1,129 @@
+use std::{fs, io, path::PathBuf};
+
+use crate::{
+    applications::{Action, Application, parse_desktop_entry},
+    entries::{get_key, read_entry, set_key},
+    mime::config_dirs,
+};
+
+/// Names of the actions of an autostart entry, the launcher runs them by name
+pub const ENABLE: &str = "Enable";
+pub const DISABLE: &str = "Disable";
+pub const REMOVE_USER_COPY: &str = "Remove user copy";
+pub const RUN_NOW: &str = "Run now";
+
+/// Entries started with the session, from the user's autostart directory first.
+/// A user entry replaces the system one with the same file name
+pub fn get_entries() -> Vec<Application> {
+    let mut apps: Vec<Application> = Vec::new();
+    for (i, dir) in config_dirs().into_iter().enumerate() {
+        let Ok(files) = fs::read_dir(format!("{}/autostart", dir)) else {
+            continue;
+        };
+        for file in files.flatten() {
+            let id = file.file_name().to_string_lossy().to_string();
+            if !id.ends_with(".desktop") || apps.iter().any(|app| app.entry == id) {
+                continue;
+            }
+            let Ok(content) = fs::read_to_string(file.path()) else {
+                continue;
+            };
+            if let Some(app) = to_item(&id, &content, i == 0) {
+                apps.push(app);
+            }
+        }
+    }
+    apps.sort_by_key(|app| app.name.to_lowercase());
+    apps
+}
+
+/// Enables or disables the entry with a user copy that sets Hidden, and X-GNOME-Autostart-enabled if it is used
+pub fn set_enabled(id: &str, enabled: bool) -> io::Result<()> {
+    let mut content = set_key(&read_autostart(id)?, "Hidden", if enabled { "false" } else { "true" });
+    if get_key(&content, "X-GNOME-Autostart-enabled").is_some() {
+        content = set_key(&content, "X-GNOME-Autostart-enabled", if enabled { "true" } else { "false" });
+    }
+    write_user_file(id, &content)
+}
+
+/// Removes the user copy, so the system entry, if any, is used again
+pub fn remove_user_copy(id: &str) -> io::Result<()> {
+    fs::remove_file(user_file(id))
+}
+
+/// Starts an application with the session, with a copy of its desktop file
+pub fn add(id: &str) -> io::Result<()> {
+    if user_file(id).exists() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} is already in autostart", id)));
+    }
+    let content = set_key(&read_entry(id)?, "Hidden", "false");
+    write_user_file(id, &content)
+}
+
+fn to_item(id: &str, content: &str, is_user: bool) -> Option<Application> {
+    let app = parse_desktop_entry(id, content)?;
+    let enabled = get_key(content, "Hidden").is_none_or(|value| value != "true")
+        && get_key(content, "X-GNOME-Autostart-enabled").is_none_or(|value| value != "false");
+    let state = if enabled { "Enabled" } else { "Disabled" };
+
+    // Shown as lines in the Info pane, below the state in the comment
+    let mut details = vec![];
+    let desktops = |key: &str| {
+        get_key(content, key).map(|value| value.split(';').filter(|d| !d.is_empty()).collect::<Vec<_>>().join(", "))
+    };
+    if let Some(only_show_in) = desktops("OnlyShowIn") {
+        details.push(format!("Only in {}", only_show_in));
+    }
+    if let Some(not_show_in) = desktops("NotShowIn") {
+        details.push(format!("Not in {}", not_show_in));
+    }
+    details.push(if is_user { "User entry" } else { "System entry" }.to_string());
+
+    let user_file = user_file(id).to_string_lossy().to_string();
+    let mut actions = vec![Action {
+        name: if enabled { DISABLE } else { ENABLE }.to_string(),
+        command: user_file.clone(),
+    }];
+    // The main command comes first in the entry's actions
+    if let Some(run) = app.actions.first().filter(|action| action.name == "Run") {
+        actions.push(Action {
+            name: RUN_NOW.to_string(),
+            command: run.command.clone(),
+        });
+    }
+    if is_user {
+        actions.push(Action {
+            name: REMOVE_USER_COPY.to_string(),
+            command: user_file,
+        });
+    }
+
+    Some(Application {
+        comment: if app.comment.is_empty() { state.to_string() } else { format!("{} · {}", state, app.comment) },
+        categories: details,
+        actions,
+        ..app
+    })
+}
+
+fn user_file(id: &str) -> PathBuf {
+    PathBuf::from(&config_dirs()[0]).join("autostart").join(id)
+}
+
+/// The autostart file used for the ID, the user copy if there is one
+fn read_autostart(id: &str) -> io::Result<String> {
+    config_dirs()
+        .into_iter()
+        .map(|dir| PathBuf::from(dir).join("autostart").join(id))
+        .find(|path| path.is_file())
+        .map(fs::read_to_string)
+        .unwrap_or_else(|| Err(io::ErrorKind::NotFound.into()))
+}
+
+fn write_user_file(id: &str, content: &str) -> io::Result<()> {
+    let path = user_file(id);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, content)
+}
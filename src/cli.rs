@@ -0,0 +1,167 @@
+use std::io::Write;
+
+use clap::ValueEnum;
+use color_eyre::eyre::{Error, eyre};
+
+use crate::{
+    applications::{Application, find_app, printed_command, spawn_app},
+    config, daemon, mime,
+};
+
+/// How `--print` writes the resolved command
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum PrintFormat {
+    /// Shell quoted, ready to paste or to pass to `sh -c`
+    Shell,
+    /// JSON array with the program and its arguments
+    Json,
+}
+
+impl PrintFormat {
+    pub fn format(&self, args: &[String]) -> String {
+        match self {
+            PrintFormat::Shell => args
+                .iter()
+                .map(|arg| shell_quote(arg))
+                .collect::<Vec<String>>()
+                .join(" "),
+            PrintFormat::Json => serde_json::to_string(args).unwrap_or_default(),
+        }
+    }
+}
+
+/// Loads the config for a command, printing the problems found to stderr
+pub fn load_config(path: Option<String>) -> config::Config {
+    let (config, warnings) = config::load_config(path);
+    for warning in warnings {
+        eprintln!("warning: {}", warning);
+    }
+    config
+}
+
+/// Prints the problems found in the config and in its theme, failing if there are any
+pub fn check_config(path: Option<String>) -> Result<(), Error> {
+    let (_, warnings) = config::load_config(path);
+    let mut stdout = std::io::stdout().lock();
+    for warning in &warnings {
+        writeln!(stdout, "{}", warning)?;
+    }
+    match warnings.len() {
+        0 => {
+            writeln!(stdout, "No problems found")?;
+            Ok(())
+        }
+        1 => Err(eyre!("Found 1 problem in the config")),
+        count => Err(eyre!("Found {} problems in the config", count)),
+    }
+}
+
+/// Writes the default config file, or prints it with `--stdout`
+pub fn init_config(path: Option<String>, force: bool, stdout: bool) -> Result<(), Error> {
+    if stdout {
+        write!(std::io::stdout(), "{}", config::default_config())?;
+        return Ok(());
+    }
+    let path = config::init_config(path, force).map_err(|e| eyre!(e))?;
+    writeln!(std::io::stdout(), "Wrote the default config to {}", path)?;
+    Ok(())
+}
+
+/// Prints every application, as tab separated `id name comment` lines or as JSON
+pub fn list(json: bool, config: &config::Config) -> Result<(), Error> {
+    print_apps(&daemon::load_apps(false, config), json)
+}
+
+/// Prints the applications matching the query, best matches first
+pub fn search_apps(query: &str, json: bool, config: &config::Config) -> Result<(), Error> {
+    print_apps(&daemon::search(query, config), json)
+}
+
+/// Runs an action of an application without opening the launcher.
+/// Without an action name, the main command of the entry is run
+pub fn launch(
+    id: &str,
+    action: Option<&str>,
+    files: &[String],
+    config: &config::Config,
+    print: Option<PrintFormat>,
+) -> Result<(), Error> {
+    // Entries hidden from the launcher can still be launched by ID
+    let apps = daemon::load_apps(true, config);
+    let app = find_app(&apps, id).ok_or_else(|| eyre!("No application with ID {}", id))?;
+
+    let action = match action {
+        Some(name) => app
+            .actions
+            .iter()
+            .find(|a| a.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                let names: Vec<&str> = app.actions.iter().map(|a| a.name.as_str()).collect();
+                eyre!("{} has no action named {}, available: {}", app.entry, name, names.join(", "))
+            })?,
+        None => app
+            .actions
+            .first()
+            .ok_or_else(|| eyre!("{} has nothing to run", app.entry))?,
+    };
+
+    run(action.command.clone(), app, files, config, print)
+}
+
+/// Opens the targets with the default application of the first one, without opening the launcher
+pub fn open_default(
+    targets: &[String],
+    config: &config::Config,
+    print: Option<PrintFormat>,
+) -> Result<(), Error> {
+    let target = targets.first().ok_or_else(|| eyre!("Nothing to open"))?;
+    let handler = mime::default_handler(target, config)?;
+    run(handler.actions[0].command.clone(), &handler, targets, config, print)
+}
+
+/// Spawns the command, or prints it if `--print` was given
+fn run(
+    command: String,
+    app: &Application,
+    targets: &[String],
+    config: &config::Config,
+    print: Option<PrintFormat>,
+) -> Result<(), Error> {
+    match print {
+        Some(format) => {
+            let args = printed_command(command, app, targets, config);
+            writeln!(std::io::stdout(), "{}", format.format(&args))?;
+            Ok(())
+        }
+        None => spawn_app(command, app, targets, config),
+    }
+}
+
+fn print_apps(apps: &[Application], json: bool) -> Result<(), Error> {
+    match write_apps(apps, json) {
+        // Piping into `head` closes stdout early, which is fine
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+/// Write errors are returned instead of panicking like `println!`
+fn write_apps(apps: &[Application], json: bool) -> std::io::Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    if json {
+        writeln!(stdout, "{}", serde_json::to_string_pretty(apps)?)?;
+    } else {
+        for app in apps {
+            writeln!(stdout, "{}\t{}\t{}", app.entry, app.name, app.comment)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn shell_quote(arg: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c);
+    if !arg.is_empty() && arg.chars().all(is_safe) {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
@@ -1,12 +1,15 @@
 use core::str;
+use std::collections::BTreeMap;
+use std::fmt;
 use std::str::FromStr;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::env;
-use toml::de::Error as TomlError;
 
 use serde::Deserialize;
-use ratatui::style::Color;
+use serde::de::DeserializeOwned;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::BorderType;
 
 /// Config struct for the application
 /// This struct is used to load the config from the config file
@@ -14,23 +17,98 @@ use ratatui::style::Color;
 struct SerializeConfig {
     icon_theme: Option<String>,
     terminal: Option<String>,
+    pinned: Option<Vec<String>>,
+    theme: Option<String>,
     appearance: Option<SerializeAppearance>,
+    emoji: Option<SerializeEmoji>,
+    session: Option<SerializeSession>,
+    files: Option<SerializeFiles>,
+    layout: Option<SerializeLayout>,
+    apps: Option<SerializeApps>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
+struct SerializeApps {
+    #[serde(rename = "override")]
+    overrides: Option<Vec<AppOverride>>,
+    custom: Option<Vec<CustomApp>>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
 struct SerializeAppearance {
-    search_input: Option<String>,
-    text: Option<String>,
-    subtext: Option<String>,
-    help_text: Option<String>,
-    selected_app: Option<String>,
-    selected_app_text: Option<String>,
-    search_border: Option<String>,
-    applications_border: Option<String>,
-    icon_border: Option<String>,
-    info_border: Option<String>,
-    actions_border: Option<String>,
-    help_border: Option<String>,
+    search_input: Option<SerializeStyle>,
+    text: Option<SerializeStyle>,
+    subtext: Option<SerializeStyle>,
+    help_text: Option<SerializeStyle>,
+    selected_app: Option<SerializeStyle>,
+    selected_app_text: Option<SerializeStyle>,
+    search_border: Option<SerializeStyle>,
+    applications_border: Option<SerializeStyle>,
+    icon_border: Option<SerializeStyle>,
+    info_border: Option<SerializeStyle>,
+    actions_border: Option<SerializeStyle>,
+    help_border: Option<SerializeStyle>,
+    match_highlight: Option<SerializeStyle>,
+    cursor: Option<SerializeStyle>,
+    selected_action: Option<SerializeStyle>,
+    error: Option<SerializeStyle>,
+    border_type: Option<String>,
+}
+
+/// A style can be just a color, or a table with colors and modifiers
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged, expecting = "expected a color, or a table with fg, bg, bold, italic, underline and reversed")]
+enum SerializeStyle {
+    Color(String),
+    Table {
+        fg: Option<String>,
+        bg: Option<String>,
+        bold: Option<bool>,
+        italic: Option<bool>,
+        underline: Option<bool>,
+        reversed: Option<bool>,
+        /// Kept to report them, the untagged enum hides them from `serde_ignored`
+        #[serde(flatten)]
+        unknown: BTreeMap<String, toml::Value>,
+    },
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct SerializeEmoji {
+    command: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct SerializeSession {
+    lock: Option<String>,
+    logout: Option<String>,
+    suspend: Option<String>,
+    hibernate: Option<String>,
+    reboot: Option<String>,
+    shutdown: Option<String>,
+    confirm: Option<bool>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct SerializeFiles {
+    roots: Option<Vec<String>>,
+    ignore: Option<Vec<String>>,
+    max_depth: Option<usize>,
+    hidden: Option<bool>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct SerializeLayout {
+    list_ratio: Option<u16>,
+    icon_ratio: Option<u16>,
+    details: Option<String>,
+    icon: Option<bool>,
+    info: Option<bool>,
+    actions: Option<bool>,
+    controls: Option<bool>,
+    compact: Option<bool>,
+    compact_width: Option<u16>,
+    compact_height: Option<u16>,
 }
 
 /// Config struct for the application.
@@ -39,70 +117,842 @@ struct SerializeAppearance {
 pub struct Config {
     pub icon_theme: String,
     pub terminal: String,
+    /// Desktop file IDs of the apps always shown first
+    pub pinned: Vec<String>,
     pub appearance: Appearance,
+    pub emoji: EmojiConfig,
+    pub session: SessionConfig,
+    pub files: FilesConfig,
+    pub layout: LayoutConfig,
+    /// Changes to single applications, from the `[[apps.override]]` tables
+    pub overrides: Vec<AppOverride>,
+    /// Entries without a desktop file, from the `[[apps.custom]]` tables
+    pub custom_apps: Vec<CustomApp>,
+    /// Absolute path of the config file, even if it doesn't exist. The daemon only answers launchers using the same one
+    pub file: Option<PathBuf>,
 }
 
-#[derive(Clone)]
-pub struct Appearance {
-    pub search_input: Color,
-    pub text: Color,
-    pub subtext: Color,
-    pub help_text: Color,
-    pub selected_app: Color,
-    pub selected_app_text: Color,
-    pub search_border: Color,
-    pub applications_border: Color,
-    pub icon_border: Color,
-    pub info_border: Color,
-    pub actions_border: Color,
-    pub help_border: Color,
-}
-
-pub fn load_config(path: Option<String>) -> Result<Config, String> {
-    let config_path = resolve_config_path(path)?;
+/// A launcher entry defined in the config
+#[derive(Deserialize, Debug, Clone)]
+pub struct CustomApp {
+    /// ID used to pin, override or launch it, the name if not set
+    pub id: Option<String>,
+    pub name: String,
+    pub comment: Option<String>,
+    pub icon: Option<String>,
+    /// Main command, with the same field codes as the Exec key of desktop files
+    pub command: Option<String>,
+    pub terminal: Option<bool>,
+    pub categories: Option<Vec<String>>,
+    pub actions: Option<Vec<CustomAction>>,
+}
 
-    if !Path::new(&config_path).exists() {
-        return Ok(Config {
+#[derive(Deserialize, Debug, Clone)]
+pub struct CustomAction {
+    pub name: String,
+    pub command: String,
+}
+
+/// Changes an application without editing its desktop file. Only the keys that are set are changed
+#[derive(Deserialize, Debug, Clone)]
+pub struct AppOverride {
+    /// Desktop file ID, the .desktop suffix is optional
+    pub id: String,
+    pub name: Option<String>,
+    pub comment: Option<String>,
+    pub icon: Option<String>,
+    /// Hide it from the launcher, it can still be launched by ID and open files
+    pub hidden: Option<bool>,
+    pub terminal: Option<bool>,
+    /// Extra environment variables
+    pub env: Option<BTreeMap<String, String>>,
+    /// Working directory, `~` is expanded to the home directory
+    pub cwd: Option<String>,
+    /// Command put before every command of the app, like `gamemoderun`
+    pub prefix: Option<String>,
+    /// Replaces the main command
+    pub exec: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
             icon_theme: "hicolor".to_string(),
             terminal: "kitty".to_string(),
-            appearance: Appearance {
-                search_input: Color::White,
-                text: Color::White,
-                subtext: Color::Gray,
-                help_text: Color::Gray,
-                selected_app: Color::Blue,
-                selected_app_text: Color::Black,
-                search_border: Color::Gray,
-                applications_border: Color::Gray,
-                icon_border: Color::Gray,
-                info_border: Color::White,
-                actions_border: Color::White,
-                help_border: Color::White,
+            pinned: vec![],
+            appearance: Appearance::default(),
+            emoji: EmojiConfig::default(),
+            session: SessionConfig::default(),
+            files: FilesConfig::default(),
+            layout: LayoutConfig::default(),
+            overrides: vec![],
+            custom_apps: vec![],
+            file: None,
+        }
+    }
+}
+
+/// A problem found while loading the config or a theme. The value it is about falls back to its default
+#[derive(Clone, Debug)]
+pub struct ConfigWarning {
+    pub file: String,
+    pub line: Option<usize>,
+    /// Dotted path of the key, like `appearance.text`
+    pub key: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file)?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+        }
+        if let Some(key) = &self.key {
+            write!(f, ": {}", key)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Collects the problems found in one file
+struct Checker<'a> {
+    file: String,
+    source: &'a str,
+    warnings: &'a mut Vec<ConfigWarning>,
+}
+
+impl Checker<'_> {
+    fn warn(&mut self, key: &[&str], message: String) {
+        self.warnings.push(ConfigWarning {
+            file: self.file.clone(),
+            line: find_line(self.source, key),
+            key: Some(key.join(".")),
+            message,
+        });
+    }
+
+    /// Parses the file, reporting the keys that are not used. A key with an invalid value is left out,
+    /// so it falls back to its default, and the file is parsed again. A file that isn't valid TOML is ignored
+    fn parse<T: DeserializeOwned>(&mut self) -> Option<T> {
+        let mut table: toml::Table = match self.source.parse() {
+            Ok(table) => table,
+            Err(e) => {
+                let e: toml::de::Error = e;
+                self.warnings.push(ConfigWarning {
+                    file: self.file.clone(),
+                    line: e.span().map(|span| self.source[..span.start].matches('\n').count() + 1),
+                    key: None,
+                    message: format!("{}, the whole file is ignored", e.message().trim_end()),
+                });
+                return None;
             }
-        })
+        };
+        // Array elements left out, as (path of the array, index in the file), to number the later ones as in the file
+        let mut removed: Vec<(Vec<String>, usize)> = vec![];
+        loop {
+            let mut unknown = Vec::new();
+            let mut on_unknown = |path: serde_ignored::Path| unknown.push(path.to_string());
+            let de = serde_ignored::Deserializer::new(toml::Value::Table(table.clone()), &mut on_unknown);
+            let e = match serde_path_to_error::deserialize::<_, T>(de) {
+                Ok(value) => {
+                    for key in unknown {
+                        // Optional values show up as `?` in the path
+                        let key: Vec<String> = key.split('.').filter(|part| *part != "?").map(String::from).collect();
+                        let key = original_path(&key, &removed);
+                        self.warn(&key.iter().map(String::as_str).collect::<Vec<&str>>(), "unknown key".to_string());
+                    }
+                    return Some(value);
+                }
+                Err(e) => e,
+            };
+            let path: Vec<String> = e
+                .path()
+                .iter()
+                .filter_map(|segment| match segment {
+                    serde_path_to_error::Segment::Seq { index } => Some(index.to_string()),
+                    serde_path_to_error::Segment::Map { key } => Some(key.clone()),
+                    _ => None,
+                })
+                .collect();
+            let key = original_path(&path, &removed);
+            let message = e.inner().message().trim_end().to_string();
+            match remove_value(&mut table, &path) {
+                Some(in_array) => {
+                    let parts: Vec<&str> = key.iter().map(String::as_str).collect();
+                    self.warn(&parts, format!("{}, it is ignored", message));
+                    if in_array && let Some((index, array)) = key.split_last() {
+                        removed.push((array.to_vec(), index.parse().unwrap_or_default()));
+                    }
+                }
+                // Not a key that can be left out, like the top level table
+                None => {
+                    self.warnings.push(ConfigWarning {
+                        file: self.file.clone(),
+                        line: None,
+                        key: None,
+                        message: format!("{}, the whole file is ignored", message),
+                    });
+                    return None;
+                }
+            }
+        }
+    }
+
+    fn check_appearance(&mut self, appearance: &SerializeAppearance, prefix: &[&str]) {
+        for (name, style) in appearance.styles() {
+            if let Some(SerializeStyle::Table { unknown, .. }) = style {
+                for key in unknown.keys() {
+                    self.warn(&[prefix, &[name, key.as_str()]].concat(), "unknown key".to_string());
+                }
+            }
+            let colors = match style {
+                Some(SerializeStyle::Color(color)) => vec![("", color)],
+                Some(SerializeStyle::Table { fg, bg, .. }) => [("fg", fg), ("bg", bg)]
+                    .into_iter()
+                    .filter_map(|(part, color)| Some((part, color.as_ref()?)))
+                    .collect(),
+                None => vec![],
+            };
+            for (part, color) in colors {
+                if parse_color(color).is_none() {
+                    let mut key = prefix.to_vec();
+                    key.push(name);
+                    if !part.is_empty() {
+                        key.push(part);
+                    }
+                    self.warn(&key, format!("invalid color `{}`", color));
+                }
+            }
+        }
+        if let Some(border_type) = &appearance.border_type
+            && !["plain", "rounded", "double", "thick", "none"].contains(&border_type.as_str())
+        {
+            let mut key = prefix.to_vec();
+            key.push("border_type");
+            self.warn(&key, format!("invalid border type `{}`, expected plain, rounded, double, thick or none", border_type));
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Appearance {
+    pub search_input: Style,
+    pub text: Style,
+    pub subtext: Style,
+    pub help_text: Style,
+    pub selected_app: Style,
+    pub selected_app_text: Style,
+    pub search_border: Style,
+    pub applications_border: Style,
+    pub icon_border: Style,
+    pub info_border: Style,
+    pub actions_border: Style,
+    pub help_border: Style,
+    /// Characters matched by the search
+    pub match_highlight: Style,
+    /// Character under the cursor in the search box
+    pub cursor: Style,
+    pub selected_action: Style,
+    /// Messages like "No icon available"
+    pub error: Style,
+    /// Border of every block, `None` draws no borders
+    pub border_type: Option<BorderType>,
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Appearance {
+            search_input: Style::new().fg(Color::White),
+            text: Style::new().fg(Color::White).add_modifier(Modifier::BOLD),
+            subtext: Style::new().fg(Color::Gray),
+            help_text: Style::new().fg(Color::Gray),
+            selected_app: Style::new().bg(Color::Blue),
+            selected_app_text: Style::new().fg(Color::Black),
+            search_border: Style::new().fg(Color::Gray),
+            applications_border: Style::new().fg(Color::Gray),
+            icon_border: Style::new().fg(Color::Gray),
+            info_border: Style::new().fg(Color::White),
+            actions_border: Style::new().fg(Color::White),
+            help_border: Style::new().fg(Color::White),
+            match_highlight: Style::new().fg(Color::Yellow),
+            cursor: Style::new().add_modifier(Modifier::REVERSED),
+            selected_action: Style::new().bg(Color::Blue).fg(Color::Black),
+            error: Style::new().fg(Color::Red).add_modifier(Modifier::BOLD),
+            border_type: Some(BorderType::Rounded),
+        }
+    }
+}
+
+/// Settings for the emoji picker mode
+#[derive(Clone, Default)]
+pub struct EmojiConfig {
+    /// Command that receives the picked character, like `wl-copy` or `wtype %s`.
+    /// If not set, the character is printed to stdout
+    pub command: Option<String>,
+}
+
+/// Commands run by the session mode
+#[derive(Clone)]
+pub struct SessionConfig {
+    pub lock: String,
+    pub logout: String,
+    pub suspend: String,
+    pub hibernate: String,
+    pub reboot: String,
+    pub shutdown: String,
+    /// Ask before logging out, rebooting or shutting down
+    pub confirm: bool,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        SessionConfig {
+            lock: "loginctl lock-session".to_string(),
+            // An empty session ID is the session loginctl runs in
+            logout: "loginctl terminate-session \"\"".to_string(),
+            suspend: "systemctl suspend".to_string(),
+            hibernate: "systemctl hibernate".to_string(),
+            reboot: "systemctl reboot".to_string(),
+            shutdown: "systemctl poweroff".to_string(),
+            confirm: true,
+        }
+    }
+}
+
+/// Where the file search mode looks for files
+#[derive(Clone)]
+pub struct FilesConfig {
+    /// Directories to search, `~` is expanded to the home directory
+    pub roots: Vec<String>,
+    /// Glob patterns matched against file and directory names to skip
+    pub ignore: Vec<String>,
+    /// How many directories deep to go below each root
+    pub max_depth: usize,
+    /// Include hidden files and directories
+    pub hidden: bool,
+}
+
+impl Default for FilesConfig {
+    fn default() -> Self {
+        FilesConfig {
+            roots: vec!["~".to_string()],
+            ignore: vec![".git".to_string(), "node_modules".to_string(), "target".to_string()],
+            max_depth: 5,
+            hidden: false,
+        }
+    }
+}
+
+/// Where the icon, info and actions panes go
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DetailsPosition {
+    Right,
+    Bottom,
+}
+
+/// Sizes and visibility of the panes
+#[derive(Clone)]
+pub struct LayoutConfig {
+    /// Percentage of the space used by the list, the details get the rest
+    pub list_ratio: u16,
+    /// Percentage of the details used by the icon
+    pub icon_ratio: u16,
+    pub details: DetailsPosition,
+    pub icon: bool,
+    pub info: bool,
+    pub actions: bool,
+    pub controls: bool,
+    /// Only show the search box and one line per entry
+    pub compact: bool,
+    /// The compact layout is also used when the terminal is narrower or shorter than this
+    pub compact_width: u16,
+    pub compact_height: u16,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        LayoutConfig {
+            list_ratio: 80,
+            icon_ratio: 25,
+            details: DetailsPosition::Right,
+            icon: true,
+            info: true,
+            actions: true,
+            controls: true,
+            compact: false,
+            compact_width: 60,
+            compact_height: 15,
+        }
+    }
+}
+
+/// Loads the config. Anything invalid falls back to its default, and the problems found are returned with the config
+pub fn load_config(path: Option<String>) -> (Config, Vec<ConfigWarning>) {
+    let file = config_file(path.clone());
+    let (config, warnings) = read_config(path);
+    (Config { file, ..config }, warnings)
+}
+
+fn read_config(path: Option<String>) -> (Config, Vec<ConfigWarning>) {
+    let mut warnings = Vec::new();
+    // Only the default path may be missing
+    let explicit = path.is_some();
+    let config_path = match resolve_config_path(path) {
+        Ok(path) => path,
+        Err(message) => {
+            warnings.push(ConfigWarning { file: "config".to_string(), line: None, key: None, message });
+            return (Config::default(), warnings);
+        }
+    };
+
+    if !explicit && !Path::new(&config_path).exists() {
+        return (Config::default(), warnings);
     }
-    
-    let config_str = read_config_file(&config_path)?;
-    let imported_conf: SerializeConfig = parse_config(&config_str)?;
 
-    Ok(Config {
+    let config_str = match read_config_file(&config_path) {
+        Ok(config_str) => config_str,
+        Err(message) => {
+            warnings.push(ConfigWarning { file: config_path, line: None, key: None, message });
+            return (Config::default(), warnings);
+        }
+    };
+    let mut checker = Checker {
+        file: config_path.clone(),
+        source: &config_str,
+        warnings: &mut warnings,
+    };
+    let Some(imported_conf) = checker.parse::<SerializeConfig>() else {
+        return (Config::default(), warnings);
+    };
+    if let Some(appearance) = &imported_conf.appearance {
+        checker.check_appearance(appearance, &["appearance"]);
+    }
+    if let Some(terminal) = &imported_conf.terminal
+        && !is_program(terminal)
+    {
+        checker.warn(&["terminal"], format!("`{}` was not found in PATH", terminal));
+    }
+    if let Some(details) = imported_conf.layout.as_ref().and_then(|l| l.details.as_deref())
+        && details != "right"
+        && details != "bottom"
+    {
+        checker.warn(&["layout", "details"], format!("invalid position `{}`, expected right or bottom", details));
+    }
+    // Entries with nothing to run are left out
+    let custom_apps: Vec<CustomApp> = imported_conf
+        .apps
+        .as_ref()
+        .and_then(|a| a.custom.clone())
+        .unwrap_or_default()
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, custom)| {
+            let has_command = custom.command.as_deref().is_some_and(|c| !c.trim().is_empty());
+            let has_actions = custom.actions.as_ref().is_some_and(|a| !a.is_empty());
+            if has_command || has_actions {
+                return Some(custom);
+            }
+            checker.warn(&["apps", "custom", &i.to_string()], "custom app needs a command or actions".to_string());
+            None
+        })
+        .collect();
+
+    let theme = match imported_conf.theme.as_deref() {
+        // A name is a file in the themes directory, not a path
+        Some(name) if name.contains('/') || name.contains("..") => {
+            checker.warn(&["theme"], format!("invalid theme name `{}`, it can't contain `/` or `..`", name));
+            None
+        }
+        Some(name) => match load_theme(name) {
+            Some((file, theme_str)) => {
+                let mut checker = Checker {
+                    file,
+                    source: &theme_str,
+                    warnings: checker.warnings,
+                };
+                let theme = checker.parse::<SerializeAppearance>();
+                if let Some(theme) = &theme {
+                    checker.check_appearance(theme, &[]);
+                }
+                theme
+            }
+            None => {
+                checker.warn(&["theme"], format!("no theme named `{}`", name));
+                None
+            }
+        },
+        None => None,
+    };
+    let default_appearance = Appearance::default();
+    // Keys set in [appearance] win over the ones from the theme
+    let appearance = match theme {
+        Some(theme) => Some(imported_conf.appearance.clone().unwrap_or_default().or(theme)),
+        None => imported_conf.appearance.clone(),
+    };
+
+    let config = Config {
         icon_theme: imported_conf.icon_theme.unwrap_or_else(|| "hicolor".to_string()),
         terminal: imported_conf.terminal.unwrap_or_else(|| "kitty".to_string()),
+        pinned: imported_conf.pinned.unwrap_or_default(),
         appearance: Appearance {
-            search_input: parse_color(imported_conf.appearance.clone().and_then(|a| a.search_input), Color::White),
-            text: parse_color(imported_conf.appearance.clone().and_then(|a| a.text), Color::White),
-            subtext: parse_color(imported_conf.appearance.clone().and_then(|a| a.subtext), Color::Gray),
-            help_text: parse_color(imported_conf.appearance.clone().and_then(|a| a.help_text), Color::Gray),
-            selected_app: parse_color(imported_conf.appearance.clone().and_then(|a| a.selected_app), Color::Blue),
-            selected_app_text: parse_color(imported_conf.appearance.clone().and_then(|a| a.selected_app_text), Color::Black),
-            search_border: parse_color(imported_conf.appearance.clone().and_then(|a| a.search_border), Color::Gray),
-            applications_border: parse_color(imported_conf.appearance.clone().and_then(|a| a.applications_border), Color::Gray),
-            icon_border: parse_color(imported_conf.appearance.clone().and_then(|a| a.icon_border), Color::Gray),
-            info_border: parse_color(imported_conf.appearance.clone().and_then(|a| a.info_border), Color::White),
-            actions_border: parse_color(imported_conf.appearance.clone().and_then(|a| a.actions_border), Color::White),
-            help_border: parse_color(imported_conf.appearance.clone().and_then(|a| a.help_border), Color::White),
+            search_input: parse_style(appearance.clone().and_then(|a| a.search_input), default_appearance.search_input, false),
+            text: parse_style(appearance.clone().and_then(|a| a.text), default_appearance.text, false),
+            subtext: parse_style(appearance.clone().and_then(|a| a.subtext), default_appearance.subtext, false),
+            help_text: parse_style(appearance.clone().and_then(|a| a.help_text), default_appearance.help_text, false),
+            selected_app: parse_style(appearance.clone().and_then(|a| a.selected_app), default_appearance.selected_app, true),
+            selected_app_text: parse_style(appearance.clone().and_then(|a| a.selected_app_text), default_appearance.selected_app_text, false),
+            search_border: parse_style(appearance.clone().and_then(|a| a.search_border), default_appearance.search_border, false),
+            applications_border: parse_style(appearance.clone().and_then(|a| a.applications_border), default_appearance.applications_border, false),
+            icon_border: parse_style(appearance.clone().and_then(|a| a.icon_border), default_appearance.icon_border, false),
+            info_border: parse_style(appearance.clone().and_then(|a| a.info_border), default_appearance.info_border, false),
+            actions_border: parse_style(appearance.clone().and_then(|a| a.actions_border), default_appearance.actions_border, false),
+            help_border: parse_style(appearance.clone().and_then(|a| a.help_border), default_appearance.help_border, false),
+            match_highlight: parse_style(appearance.clone().and_then(|a| a.match_highlight), default_appearance.match_highlight, false),
+            cursor: parse_style(appearance.clone().and_then(|a| a.cursor), default_appearance.cursor, false),
+            // Follows the selected app unless it is set
+            selected_action: parse_style(
+                appearance.clone().and_then(|a| a.selected_action),
+                parse_style(appearance.clone().and_then(|a| a.selected_app), default_appearance.selected_app, true)
+                    .patch(parse_style(appearance.clone().and_then(|a| a.selected_app_text), default_appearance.selected_app_text, false)),
+                true,
+            ),
+            error: parse_style(appearance.clone().and_then(|a| a.error), default_appearance.error, false),
+            border_type: parse_border_type(appearance.clone().and_then(|a| a.border_type), default_appearance.border_type),
         },
-    })
+        emoji: EmojiConfig {
+            command: imported_conf.emoji.and_then(|e| e.command),
+        },
+        session: parse_session(imported_conf.session),
+        files: parse_files(imported_conf.files),
+        layout: parse_layout(imported_conf.layout),
+        overrides: imported_conf.apps.clone().and_then(|a| a.overrides).unwrap_or_default(),
+        custom_apps,
+        file: None,
+    };
+    (config, warnings)
+}
+
+/// Writes the default config to the config path, with every key and what it does.
+/// An existing file is only replaced if `force` is set. Returns the path of the file
+pub fn init_config(path: Option<String>, force: bool) -> Result<String, String> {
+    let config_path = resolve_config_path(path)?;
+    if Path::new(&config_path).exists() && !force {
+        return Err(format!("{} already exists, use --force to replace it", config_path));
+    }
+    if let Some(dir) = Path::new(&config_path).parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    fs::write(&config_path, default_config())
+        .map_err(|e| format!("Failed to write {}: {}", config_path, e))?;
+    Ok(config_path)
+}
+
+/// The config file with every key and its default, generated from the defaults used by `load_config`
+pub fn default_config() -> String {
+    let config = Config::default();
+    let appearance = &config.appearance;
+    let session = &config.session;
+    let files = &config.files;
+    let layout = &config.layout;
+    format!(
+        r#"# termlaunch configuration, every key shows its default value
+
+# Icon theme used for the application icons
+icon_theme = {icon_theme}
+# Terminal emulator for terminal applications, run as `<terminal> -e <command>`
+terminal = {terminal}
+# Desktop file IDs of the apps always shown first
+pinned = {pinned}
+# Built-in theme, like catppuccin-mocha, nord or terminal, or the name of a file in
+# $XDG_CONFIG_HOME/termlaunch/themes. The keys set in [appearance] override it
+# theme = "catppuccin-mocha"
+
+# Colors can be hex codes, names like "red" or "lightblue", ANSI indexes like "208"
+# or "reset". Every key also takes a table like {{ fg = "white", bg = "blue", bold = true,
+# italic = false, underline = false, reversed = false }}. The keys are commented out so
+# the theme applies, uncomment the ones to change
+[appearance]
+# search_input = {search_input}
+# text = {text}
+# subtext = {subtext}
+# help_text = {help_text}
+# A plain color sets the background of the selected entry
+# selected_app = {selected_app}
+# selected_app_text = {selected_app_text}
+# search_border = {search_border}
+# applications_border = {applications_border}
+# icon_border = {icon_border}
+# info_border = {info_border}
+# actions_border = {actions_border}
+# help_border = {help_border}
+# Characters matched by the search
+# match_highlight = {match_highlight}
+# Character under the cursor in the search box
+# cursor = {cursor}
+# A plain color sets the background of the selected action. Follows selected_app
+# and selected_app_text unless it is set
+# selected_action = {selected_action}
+# Messages like "No icon available"
+# error = {error}
+# plain, rounded, double, thick or none
+# border_type = {border_type}
+
+[emoji]
+# Shell command that receives the picked character, run once the launcher is
+# closed. If it contains %s, it is replaced by the character, already quoted,
+# otherwise the character is written to its stdin. If not set, the character is
+# printed
+# command = "wl-copy"
+
+# Commands run by the session mode
+[session]
+lock = {lock}
+logout = {logout}
+suspend = {suspend}
+hibernate = {hibernate}
+reboot = {reboot}
+shutdown = {shutdown}
+# Ask before logging out, rebooting or shutting down
+confirm = {confirm}
+
+# Where the file search mode looks for files
+[files]
+# Directories to search, ~ is the home directory
+roots = {roots}
+# Glob patterns matched against file and directory names to skip
+ignore = {ignore}
+# How many directories deep to go below each root
+max_depth = {max_depth}
+# Include hidden files and directories
+hidden = {hidden}
+
+# Sizes and visibility of the panes
+[layout]
+# Percentage of the space used by the list, the details get the rest
+list_ratio = {list_ratio}
+# Percentage of the details used by the icon
+icon_ratio = {icon_ratio}
+# right or bottom
+details = {details}
+icon = {icon}
+info = {info}
+actions = {actions}
+controls = {controls}
+# Only show the search box and one line per entry
+compact = {compact}
+# The compact layout is also used when the terminal is smaller than this
+compact_width = {compact_width}
+compact_height = {compact_height}
+
+# Entries for commands that have no desktop file. Every key but name is optional,
+# but an entry needs a command or actions
+# [[apps.custom]]
+# id = "deploy"
+# name = "Deploy"
+# comment = "Deploy the current branch"
+# icon = "utilities-terminal"
+# command = "deploy.sh"
+# terminal = true
+# categories = ["Development"]
+# actions = [{{ name = "Staging", command = "deploy.sh --staging" }}]
+
+# Changes to single applications, without editing their desktop files. Every key
+# but id is optional
+# [[apps.override]]
+# id = "firefox.desktop"
+# name = "Firefox"
+# comment = "Web browser"
+# icon = "firefox-developer-edition"
+# hidden = false
+# terminal = false
+# env = {{ MOZ_ENABLE_WAYLAND = "1" }}
+# cwd = "~/Downloads"
+# prefix = "gamemoderun"
+# exec = "firefox --private-window %u"
+"#,
+        icon_theme = toml_value(&config.icon_theme),
+        terminal = toml_value(&config.terminal),
+        pinned = toml_value(&config.pinned),
+        search_input = style_value(appearance.search_input, false),
+        text = style_value(appearance.text, false),
+        subtext = style_value(appearance.subtext, false),
+        help_text = style_value(appearance.help_text, false),
+        selected_app = style_value(appearance.selected_app, true),
+        selected_app_text = style_value(appearance.selected_app_text, false),
+        search_border = style_value(appearance.search_border, false),
+        applications_border = style_value(appearance.applications_border, false),
+        icon_border = style_value(appearance.icon_border, false),
+        info_border = style_value(appearance.info_border, false),
+        actions_border = style_value(appearance.actions_border, false),
+        help_border = style_value(appearance.help_border, false),
+        match_highlight = style_value(appearance.match_highlight, false),
+        cursor = style_value(appearance.cursor, false),
+        selected_action = style_value(appearance.selected_action, true),
+        error = style_value(appearance.error, false),
+        border_type = toml_value(match appearance.border_type {
+            Some(BorderType::Plain) => "plain",
+            Some(BorderType::Double) => "double",
+            Some(BorderType::Thick) => "thick",
+            None => "none",
+            _ => "rounded",
+        }),
+        lock = toml_value(&session.lock),
+        logout = toml_value(&session.logout),
+        suspend = toml_value(&session.suspend),
+        hibernate = toml_value(&session.hibernate),
+        reboot = toml_value(&session.reboot),
+        shutdown = toml_value(&session.shutdown),
+        confirm = session.confirm,
+        roots = toml_value(&files.roots),
+        ignore = toml_value(&files.ignore),
+        max_depth = files.max_depth,
+        hidden = files.hidden,
+        list_ratio = layout.list_ratio,
+        icon_ratio = layout.icon_ratio,
+        details = toml_value(match layout.details {
+            DetailsPosition::Right => "right",
+            DetailsPosition::Bottom => "bottom",
+        }),
+        icon = layout.icon,
+        info = layout.info,
+        actions = layout.actions,
+        controls = layout.controls,
+        compact = layout.compact,
+        compact_width = layout.compact_width,
+        compact_height = layout.compact_height,
+    )
+}
+
+/// A string or a list of strings, quoted as TOML
+fn toml_value<T: serde::Serialize + ?Sized>(value: &T) -> String {
+    toml::Value::try_from(value).map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// A style as the config writes it: just a color when that is enough, a table otherwise
+fn style_value(style: Style, background: bool) -> String {
+    let color = |color: Color| toml_value(&color.to_string().to_lowercase());
+    let modifiers = style.add_modifier;
+    match (style.fg, style.bg) {
+        (Some(fg), None) if modifiers.is_empty() && !background => return color(fg),
+        (None, Some(bg)) if modifiers.is_empty() && background => return color(bg),
+        _ => {}
+    }
+    let mut fields = vec![];
+    if let Some(fg) = style.fg {
+        fields.push(format!("fg = {}", color(fg)));
+    }
+    if let Some(bg) = style.bg {
+        fields.push(format!("bg = {}", color(bg)));
+    }
+    for (name, modifier) in [
+        ("bold", Modifier::BOLD),
+        ("italic", Modifier::ITALIC),
+        ("underline", Modifier::UNDERLINED),
+        ("reversed", Modifier::REVERSED),
+    ] {
+        if modifiers.contains(modifier) {
+            fields.push(format!("{} = true", name));
+        }
+    }
+    format!("{{ {} }}", fields.join(", "))
+}
+
+/// Built-in themes, in the same format as the theme files
+const THEMES: [(&str, &str); 9] = [
+    ("catppuccin-latte", include_str!("themes/catppuccin-latte.toml")),
+    ("catppuccin-frappe", include_str!("themes/catppuccin-frappe.toml")),
+    ("catppuccin-macchiato", include_str!("themes/catppuccin-macchiato.toml")),
+    ("catppuccin-mocha", include_str!("themes/catppuccin-mocha.toml")),
+    ("gruvbox", include_str!("themes/gruvbox.toml")),
+    ("nord", include_str!("themes/nord.toml")),
+    ("dracula", include_str!("themes/dracula.toml")),
+    ("tokyo-night", include_str!("themes/tokyo-night.toml")),
+    ("terminal", include_str!("themes/terminal.toml")),
+];
+
+impl SerializeAppearance {
+    fn styles(&self) -> [(&'static str, &Option<SerializeStyle>); 16] {
+        [
+            ("search_input", &self.search_input),
+            ("text", &self.text),
+            ("subtext", &self.subtext),
+            ("help_text", &self.help_text),
+            ("selected_app", &self.selected_app),
+            ("selected_app_text", &self.selected_app_text),
+            ("search_border", &self.search_border),
+            ("applications_border", &self.applications_border),
+            ("icon_border", &self.icon_border),
+            ("info_border", &self.info_border),
+            ("actions_border", &self.actions_border),
+            ("help_border", &self.help_border),
+            ("match_highlight", &self.match_highlight),
+            ("cursor", &self.cursor),
+            ("selected_action", &self.selected_action),
+            ("error", &self.error),
+        ]
+    }
+
+    /// Fills the keys that are not set with the ones from `base`
+    fn or(self, base: SerializeAppearance) -> SerializeAppearance {
+        SerializeAppearance {
+            search_input: self.search_input.or(base.search_input),
+            text: self.text.or(base.text),
+            subtext: self.subtext.or(base.subtext),
+            help_text: self.help_text.or(base.help_text),
+            selected_app: self.selected_app.or(base.selected_app),
+            selected_app_text: self.selected_app_text.or(base.selected_app_text),
+            search_border: self.search_border.or(base.search_border),
+            applications_border: self.applications_border.or(base.applications_border),
+            icon_border: self.icon_border.or(base.icon_border),
+            info_border: self.info_border.or(base.info_border),
+            actions_border: self.actions_border.or(base.actions_border),
+            help_border: self.help_border.or(base.help_border),
+            match_highlight: self.match_highlight.or(base.match_highlight),
+            cursor: self.cursor.or(base.cursor),
+            selected_action: self.selected_action.or(base.selected_action),
+            error: self.error.or(base.error),
+            border_type: self.border_type.or(base.border_type),
+        }
+    }
+}
+
+/// Reads a theme from $XDG_CONFIG_HOME/termlaunch/themes/<name>.toml, or the built-in one with that name.
+/// Returns the file it comes from and its content
+fn load_theme(name: &str) -> Option<(String, String)> {
+    if let Ok(dir) = themes_dir() {
+        let path = format!("{}/{}.toml", dir, name);
+        if let Ok(theme_str) = fs::read_to_string(&path) {
+            return Some((path, theme_str));
+        }
+    }
+    THEMES
+        .iter()
+        .find(|(theme, _)| *theme == name)
+        .map(|(_, content)| (format!("built-in theme {}", name), content.to_string()))
+}
+
+/// The config file and the user's theme files, to reload the config when one of them changes
+pub fn watched_files(path: Option<String>) -> Vec<PathBuf> {
+    let mut files = vec![];
+    if let Some(config_path) = config_file(path) {
+        files.push(config_path);
+    }
+    if let Ok(dir) = themes_dir() {
+        files.push(PathBuf::from(dir).join("*.toml"));
+    }
+    files
+}
+
+fn themes_dir() -> Result<String, String> {
+    let home = env::var("HOME").map_err(|_| "HOME environment variable not set".to_string())?;
+    let xdg_config_home = env::var("XDG_CONFIG_HOME").unwrap_or_else(|_| format!("{}/.config", home));
+    Ok(format!("{}/termlaunch/themes", xdg_config_home))
+}
+
+/// Absolute path of the config file. A relative path like `-c config.toml` has no directory to watch otherwise
+fn config_file(path: Option<String>) -> Option<PathBuf> {
+    std::path::absolute(resolve_config_path(path).ok()?).ok()
 }
 
 fn resolve_config_path(path: Option<String>) -> Result<String, String> {
@@ -123,18 +973,237 @@ fn read_config_file(path: &str) -> Result<String, String> {
     }
 }
 
-fn parse_config(config_str: &str) -> Result<SerializeConfig, String> {
-    toml::from_str(config_str).map_err(|e: TomlError| format!("Failed to parse config file: {}", e))
+/// Removes the value at the path, returning whether it was an array element, or `None` if there is nothing to remove
+fn remove_value(table: &mut toml::Table, path: &[String]) -> Option<bool> {
+    let (last, parents) = path.split_last()?;
+    let Some((first, parents)) = parents.split_first() else {
+        return table.remove(last).map(|_| false);
+    };
+    let mut value = table.get_mut(first)?;
+    for part in parents {
+        value = match value {
+            toml::Value::Table(table) => table.get_mut(part)?,
+            toml::Value::Array(array) => array.get_mut(part.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    match value {
+        toml::Value::Table(table) => table.remove(last).map(|_| false),
+        toml::Value::Array(array) => {
+            let index: usize = last.parse().ok()?;
+            (index < array.len()).then(|| array.remove(index)).map(|_| true)
+        }
+        _ => None,
+    }
+}
+
+/// Path of a key as numbered in the file, from its path once the array elements in `removed` were left out
+fn original_path(path: &[String], removed: &[(Vec<String>, usize)]) -> Vec<String> {
+    let mut original: Vec<String> = vec![];
+    for part in path {
+        let part = match part.parse::<usize>() {
+            Ok(mut index) => {
+                let mut indexes: Vec<usize> = removed
+                    .iter()
+                    .filter(|(array, _)| *array == original)
+                    .map(|(_, index)| *index)
+                    .collect();
+                indexes.sort();
+                for removed_index in indexes {
+                    if removed_index <= index {
+                        index += 1;
+                    }
+                }
+                index.to_string()
+            }
+            Err(_) => part.clone(),
+        };
+        original.push(part);
+    }
+    original
+}
+
+/// Line of a key from its path, like `["appearance", "text"]`.
+/// Keys inside inline tables give the line of the table
+fn find_line(source: &str, key: &[&str]) -> Option<usize> {
+    let mut table: Vec<String> = vec![];
+    // Tables in arrays like [[apps.override]] are numbered in the path, from 0
+    let mut array_lengths: BTreeMap<String, usize> = BTreeMap::new();
+    let mut parent_line = None;
+    for (i, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if let Some(header) = line.strip_prefix('[') {
+            let is_array = header.starts_with('[');
+            let header = header.trim_start_matches('[').split(']').next().unwrap_or_default();
+            table = header.split('.').map(|part| part.trim().trim_matches('"').to_string()).collect();
+            if is_array {
+                let length = array_lengths.entry(table.join(".")).or_default();
+                table.push(length.to_string());
+                *length += 1;
+            }
+            if table == key {
+                return Some(i + 1);
+            }
+            continue;
+        }
+        let Some((name, _)) = line.split_once('=') else {
+            continue;
+        };
+        let path: Vec<&str> = table
+            .iter()
+            .map(|part| part.as_str())
+            .chain(name.split('.').map(|part| part.trim().trim_matches('"')))
+            .collect();
+        if path == key {
+            return Some(i + 1);
+        }
+        if key.starts_with(&path) {
+            parent_line = Some(i + 1);
+        }
+    }
+    parent_line
+}
+
+/// Whether the program is a path to a file or can be found in PATH
+fn is_program(program: &str) -> bool {
+    if program.contains('/') {
+        return Path::new(program).is_file();
+    }
+    env::var("PATH")
+        .unwrap_or_default()
+        .split(':')
+        .any(|dir| Path::new(dir).join(program).is_file())
 }
 
-fn parse_color(color: Option<String>, default: Color) -> Color {
-    color
-        .filter(|c| is_valid_color(c))
-        .and_then(|c| Color::from_str(&c).ok())
-        .unwrap_or(default)
+fn parse_session(session: Option<SerializeSession>) -> SessionConfig {
+    let default = SessionConfig::default();
+    let Some(session) = session else {
+        return default;
+    };
+    SessionConfig {
+        lock: session.lock.unwrap_or(default.lock),
+        logout: session.logout.unwrap_or(default.logout),
+        suspend: session.suspend.unwrap_or(default.suspend),
+        hibernate: session.hibernate.unwrap_or(default.hibernate),
+        reboot: session.reboot.unwrap_or(default.reboot),
+        shutdown: session.shutdown.unwrap_or(default.shutdown),
+        confirm: session.confirm.unwrap_or(default.confirm),
+    }
 }
 
+fn parse_files(files: Option<SerializeFiles>) -> FilesConfig {
+    let default = FilesConfig::default();
+    let Some(files) = files else {
+        return default;
+    };
+    FilesConfig {
+        roots: files.roots.unwrap_or(default.roots),
+        ignore: files.ignore.unwrap_or(default.ignore),
+        max_depth: files.max_depth.unwrap_or(default.max_depth),
+        hidden: files.hidden.unwrap_or(default.hidden),
+    }
+}
 
-fn is_valid_color(color: &str) -> bool {
-    color.len() == 7 && color.starts_with('#') && color.chars().skip(1).all(|c| c.is_ascii_hexdigit())
-}
\ No newline at end of file
+fn parse_layout(layout: Option<SerializeLayout>) -> LayoutConfig {
+    let default = LayoutConfig::default();
+    let Some(layout) = layout else {
+        return default;
+    };
+    LayoutConfig {
+        list_ratio: layout.list_ratio.unwrap_or(default.list_ratio).min(100),
+        icon_ratio: layout.icon_ratio.unwrap_or(default.icon_ratio).min(100),
+        details: match layout.details.as_deref() {
+            Some("bottom") => DetailsPosition::Bottom,
+            _ => DetailsPosition::Right,
+        },
+        icon: layout.icon.unwrap_or(default.icon),
+        info: layout.info.unwrap_or(default.info),
+        actions: layout.actions.unwrap_or(default.actions),
+        controls: layout.controls.unwrap_or(default.controls),
+        compact: layout.compact.unwrap_or(default.compact),
+        compact_width: layout.compact_width.unwrap_or(default.compact_width),
+        compact_height: layout.compact_height.unwrap_or(default.compact_height),
+    }
+}
+
+/// Accepts every color form ratatui understands: names like `red` or `reset`, ANSI indexes like `4` and `#rrggbb`
+fn parse_color(color: &str) -> Option<Color> {
+    Color::from_str(color).ok()
+}
+
+/// Applies the configured style on top of the default one. A plain color sets the foreground,
+/// or the background for elements that are `background` colors, like the selected app
+fn parse_style(style: Option<SerializeStyle>, default: Style, background: bool) -> Style {
+    match style {
+        None => default,
+        Some(SerializeStyle::Color(color)) => match parse_color(&color) {
+            Some(color) if background => default.bg(color),
+            Some(color) => default.fg(color),
+            None => default,
+        },
+        Some(SerializeStyle::Table { fg, bg, bold, italic, underline, reversed, .. }) => {
+            let mut style = default;
+            if let Some(fg) = fg.as_deref().and_then(parse_color) {
+                style = style.fg(fg);
+            }
+            if let Some(bg) = bg.as_deref().and_then(parse_color) {
+                style = style.bg(bg);
+            }
+            for (enabled, modifier) in [
+                (bold, Modifier::BOLD),
+                (italic, Modifier::ITALIC),
+                (underline, Modifier::UNDERLINED),
+                (reversed, Modifier::REVERSED),
+            ] {
+                style = match enabled {
+                    Some(true) => style.add_modifier(modifier),
+                    Some(false) => style.remove_modifier(modifier),
+                    None => style,
+                };
+            }
+            style
+        }
+    }
+}
+
+fn parse_border_type(border_type: Option<String>, default: Option<BorderType>) -> Option<BorderType> {
+    match border_type.as_deref() {
+        Some("plain") => Some(BorderType::Plain),
+        Some("rounded") => Some(BorderType::Rounded),
+        Some("double") => Some(BorderType::Double),
+        Some("thick") => Some(BorderType::Thick),
+        Some("none") => None,
+        _ => default,
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"terminal = "foot"
+
+[appearance]
+text = { fg = "red", bolt = true }
+
+[[apps.custom]]
+name = "One"
+
+[[apps.custom]]
+name = "Two"
+env.FOO = "1"
+"#;
+
+    #[test]
+    fn find_line_numbers_array_tables_from_zero() {
+        assert_eq!(find_line(SOURCE, &["terminal"]), Some(1));
+        assert_eq!(find_line(SOURCE, &["apps", "custom", "0"]), Some(6));
+        assert_eq!(find_line(SOURCE, &["apps", "custom", "1", "name"]), Some(10));
+        assert_eq!(find_line(SOURCE, &["apps", "custom", "2"]), None);
+    }
+
+    #[test]
+    fn find_line_gives_the_line_of_inline_tables_and_dotted_keys() {
+        assert_eq!(find_line(SOURCE, &["appearance", "text", "bolt"]), Some(4));
+        assert_eq!(find_line(SOURCE, &["apps", "custom", "1", "env", "FOO"]), Some(11));
+    }
+}
@@ -0,0 +1,190 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    io::{self, BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use serde::de::DeserializeOwned;
+
+use crate::{
+    applications::{self, Application, get_app_icon},
+    cli,
+    config::{Config, watched_files},
+    instance, watch,
+};
+
+/// Entries and icons kept in memory by the daemon, so launching doesn't read every desktop file again
+struct State {
+    config_path: Option<String>,
+    config: Config,
+    /// Every entry, including the hidden ones
+    apps: Vec<Application>,
+    /// Icon paths already looked up, by icon name
+    icons: HashMap<String, Option<PathBuf>>,
+}
+
+impl State {
+    fn load(config_path: Option<String>) -> Self {
+        let config = cli::load_config(config_path.clone());
+        let apps = applications::load_apps(true, &config);
+        State {
+            config_path,
+            config,
+            apps,
+            icons: HashMap::new(),
+        }
+    }
+
+    fn reload(&mut self) {
+        *self = State::load(self.config_path.take());
+    }
+
+    /// Reads the saved files again. Only the changed entries are read if every file is a desktop file
+    fn update(&mut self, files: &BTreeSet<PathBuf>) {
+        if files.iter().any(|file| file.extension().is_none_or(|e| e != "desktop")) {
+            self.reload();
+            return;
+        }
+        for file in files {
+            if let Some(id) = file.file_name() {
+                applications::update_app(&mut self.apps, &id.to_string_lossy(), true, &self.config);
+            }
+        }
+    }
+
+    fn visible_apps(&self) -> Vec<Application> {
+        self.apps.iter().filter(|app| !app.hidden).cloned().collect()
+    }
+
+    /// Answers a request with JSON, see `request`
+    fn answer(&mut self, request: &str) -> serde_json::Result<String> {
+        match request.split_once(' ').unwrap_or((request, "")) {
+            ("apps", "hidden") => serde_json::to_string(&self.apps),
+            ("apps", _) => serde_json::to_string(&self.visible_apps()),
+            ("search", query) => serde_json::to_string(&applications::search(&self.visible_apps(), query)),
+            ("icon", name) => {
+                let config = &self.config;
+                let path = self
+                    .icons
+                    .entry(name.to_string())
+                    .or_insert_with(|| get_app_icon(name.to_string(), config));
+                serde_json::to_string(path)
+            }
+            ("reload", _) => {
+                self.reload();
+                serde_json::to_string(&true)
+            }
+            _ => serde_json::to_string(&()),
+        }
+    }
+}
+
+fn socket_path() -> PathBuf {
+    instance::runtime_dir().join("daemon.sock")
+}
+
+/// Keeps the entries in memory and answers the launchers on the daemon socket until it is killed.
+/// The entries are read again when a desktop file, the config or a theme is saved or removed
+pub fn run(config_path: Option<String>) -> io::Result<()> {
+    let socket = instance::create_runtime_dir()?.join("daemon.sock");
+    if UnixStream::connect(&socket).is_ok() {
+        return Err(io::Error::other("The daemon is already running"));
+    }
+    // Left behind by a daemon that was killed
+    let _ = fs::remove_file(&socket);
+    let listener = UnixListener::bind(&socket)?;
+
+    let mut files = watched_files(config_path.clone());
+    files.extend(applications::entry_files());
+    let state = Arc::new(Mutex::new(State::load(config_path)));
+    if let Some(changes) = watch::spawn_watcher(files) {
+        let state = Arc::clone(&state);
+        thread::spawn(move || {
+            while let Ok(file) = changes.recv() {
+                // Installing a package often writes many files at once
+                let mut files = BTreeSet::from([file]);
+                while let Ok(file) = changes.recv_timeout(Duration::from_millis(200)) {
+                    files.insert(file);
+                }
+                if let Ok(mut state) = state.lock() {
+                    state.update(&files);
+                }
+            }
+        });
+    }
+
+    for stream in listener.incoming().flatten() {
+        // A launcher that never sends its request doesn't block the others for long
+        let _ = stream.set_read_timeout(Some(Duration::from_secs(1)));
+        let mut reader = BufReader::new(&stream);
+        let (mut config_file, mut request) = (String::new(), String::new());
+        if reader.read_line(&mut config_file).is_err() || reader.read_line(&mut request).is_err() {
+            continue;
+        }
+        let Ok(mut state) = state.lock() else {
+            continue;
+        };
+        // Closing without an answer makes a launcher with another config load the entries itself
+        if config_file.trim_end_matches('\n') != config_line(&state.config) {
+            continue;
+        }
+        let Ok(response) = state.answer(request.trim_end_matches('\n')) else {
+            continue;
+        };
+        let _ = (&stream).write_all(response.as_bytes());
+    }
+    Ok(())
+}
+
+/// First line of a request, the config file of the launcher
+fn config_line(config: &Config) -> String {
+    config
+        .file
+        .as_ref()
+        .map(|file| file.to_string_lossy().replace('\n', " "))
+        .unwrap_or_default()
+}
+
+/// Sends a request to the daemon and reads its answer, `None` if the daemon is not running, uses another config file
+/// or doesn't answer in time. A request is the config file on a line, then a line with `apps`, `apps hidden`,
+/// `search <query>`, `icon <name>` or `reload`
+fn request<T: DeserializeOwned>(request: &str, config: &Config) -> Option<T> {
+    let mut stream = UnixStream::connect(socket_path()).ok()?;
+    // The answer has the commands that are run, so only a daemon started by the same user is trusted
+    let peer = rustix::net::sockopt::socket_peercred(&stream).ok()?;
+    if peer.uid != rustix::process::getuid() {
+        return None;
+    }
+    // A daemon that is stopped or busy reloading is not waited for, the caller loads the entries itself
+    stream.set_read_timeout(Some(Duration::from_millis(500))).ok()?;
+    stream.set_write_timeout(Some(Duration::from_millis(500))).ok()?;
+    writeln!(stream, "{}\n{}", config_line(config), request.replace('\n', " ")).ok()?;
+    serde_json::from_reader(BufReader::new(stream)).ok()
+}
+
+/// The entries from the daemon if it is running, otherwise read from the desktop files
+pub fn load_apps(include_no_display: bool, config: &Config) -> Vec<Application> {
+    let request_line = if include_no_display { "apps hidden" } else { "apps" };
+    request(request_line, config).unwrap_or_else(|| applications::load_apps(include_no_display, config))
+}
+
+/// The entries matching the query, ranked by the daemon if it is running
+pub fn search(query: &str, config: &Config) -> Vec<Application> {
+    request(&format!("search {}", query), config)
+        .unwrap_or_else(|| applications::search(&applications::get_apps(config), query))
+}
+
+/// Path of the icon, looked up once by the daemon if it is running
+pub fn app_icon(name: String, config: &Config) -> Option<PathBuf> {
+    request(&format!("icon {}", name), config).unwrap_or_else(|| get_app_icon(name, config))
+}
+
+/// Tells the daemon to read the entries again, after the launcher changed a desktop file
+pub fn reload(config: &Config) {
+    let _: Option<bool> = request("reload", config);
+}
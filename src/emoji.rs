@@ -0,0 +1,79 @@
+use std::{
+    io::Write,
+    os::unix::process::CommandExt,
+    process::{Command, Stdio},
+};
+
+use color_eyre::eyre::Error;
+
+use crate::{
+    applications::{Action, Application},
+    cli::shell_quote,
+};
+
+/// Bundled table of emoji and symbols, one `code points;name;keywords` per line.
+/// Flags, skin tones and ZWJ sequences are made of several code points, separated by spaces
+const UNICODE_TABLE: &str = include_str!("unicode.txt");
+
+pub fn get_chars() -> Vec<Application> {
+    UNICODE_TABLE
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split(';');
+            let codepoints: Vec<&str> = fields.next()?.split(' ').collect();
+            let name = fields.next()?;
+            let keywords = fields.next().unwrap_or_default();
+            let glyph = codepoints
+                .iter()
+                .map(|codepoint| char::from_u32(u32::from_str_radix(codepoint, 16).ok()?))
+                .collect::<Option<String>>()?;
+            let codepoints = codepoints
+                .iter()
+                .map(|codepoint| format!("U+{}", codepoint))
+                .collect::<Vec<String>>()
+                .join(" ");
+
+            Some(Application {
+                entry: codepoints.clone(),
+                name: name.to_string(),
+                comment: codepoints,
+                icon: glyph.clone(),
+                keywords: keywords.split(' ').map(|k| k.to_string()).collect(),
+                actions: vec![Action {
+                    name: "Pick".to_string(),
+                    command: glyph,
+                }],
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// Sends the picked character to the configured command, run by the shell.
+/// This runs after the terminal is restored, and the command is not waited for, so the launcher
+/// can close and give the focus back before tools like `wtype` type the character
+pub fn pick(glyph: &str, command: &str) -> Result<(), Error> {
+    // If the command has a %s placeholder, the character goes there, otherwise it is written to stdin
+    let mut child = Command::new("sh");
+    child
+        .arg("-c")
+        .arg(command.replace("%s", &shell_quote(glyph)))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    unsafe {
+        child.pre_exec(|| {
+            rustix::process::setsid().ok();
+            Ok(())
+        });
+    }
+    if command.contains("%s") {
+        child.stdin(Stdio::null()).spawn()?;
+    } else {
+        let mut child = child.stdin(Stdio::piped()).spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(glyph.as_bytes())?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,242 @@
+use std::{fs, io, path::PathBuf};
+
+use crate::mime::data_dirs;
+
+/// Keys of the desktop entry shown in the editor
+pub const FIELDS: [&str; 6] = ["Name", "Comment", "Icon", "Exec", "Terminal", "Categories"];
+
+/// Desktop entry being edited or created in the launcher
+pub struct EntryForm {
+    /// Desktop file ID, `None` for a new entry
+    pub id: Option<String>,
+    /// Values of `FIELDS`, in the same order
+    pub values: Vec<String>,
+    pub selected: usize,
+    /// Why the last save, hide or reset failed
+    pub error: Option<String>,
+}
+
+impl EntryForm {
+    /// Form with the values of the desktop file currently used for the ID
+    pub fn edit(id: &str) -> io::Result<Self> {
+        let content = fs::read_to_string(source_file(id).ok_or(io::ErrorKind::NotFound)?)?;
+        Ok(EntryForm {
+            id: Some(id.to_string()),
+            values: FIELDS
+                .iter()
+                .map(|key| match get_key(&content, key) {
+                    Some(value) => value,
+                    None if *key == "Terminal" => "false".to_string(),
+                    None => String::new(),
+                })
+                .collect(),
+            selected: 0,
+            error: None,
+        })
+    }
+
+    pub fn create() -> Self {
+        EntryForm {
+            id: None,
+            values: FIELDS.iter().map(|key| if *key == "Terminal" { "false" } else { "" }.to_string()).collect(),
+            selected: 0,
+            error: None,
+        }
+    }
+
+    pub fn value_mut(&mut self) -> &mut String {
+        &mut self.values[self.selected]
+    }
+
+    /// Writes the user copy of the entry, keeping the keys the form doesn't show.
+    /// Returns the desktop file ID
+    pub fn save(&self) -> Result<String, String> {
+        let value = |key: &str| FIELDS.iter().position(|k| *k == key).map(|i| self.values[i].trim()).unwrap_or_default();
+        if value("Name").is_empty() {
+            return Err("Name can't be empty".to_string());
+        }
+        if value("Exec").is_empty() {
+            return Err("Exec can't be empty".to_string());
+        }
+        if value("Terminal") != "true" && value("Terminal") != "false" {
+            return Err("Terminal must be true or false".to_string());
+        }
+
+        let (id, mut content) = match &self.id {
+            Some(id) => (id.clone(), read_entry(id).map_err(|e| e.to_string())?),
+            None => {
+                let slug = slug(value("Name"));
+                if slug.is_empty() {
+                    return Err("Name needs a letter or a digit, it is used for the file name".to_string());
+                }
+                let id = format!("{}.desktop", slug);
+                if user_file(&id).exists() {
+                    return Err(format!("{} already exists", id));
+                }
+                (id, "[Desktop Entry]\nType=Application\n".to_string())
+            }
+        };
+        for key in FIELDS {
+            content = set_key(&content, key, value(key));
+        }
+        write_user_file(&id, &content).map_err(|e| e.to_string())?;
+        Ok(id)
+    }
+}
+
+/// Hides the entry from the launcher and other menus, with a user copy that sets NoDisplay
+pub fn hide(id: &str) -> io::Result<()> {
+    let content = set_key(&read_entry(id)?, "NoDisplay", "true");
+    write_user_file(id, &content)
+}
+
+/// Removes the user copy, so the system entry is used again. Returns whether there was one
+pub fn reset(id: &str) -> io::Result<bool> {
+    match fs::remove_file(user_file(id)) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Whether the entry has a user copy that `reset` would remove
+pub fn has_user_file(id: &str) -> bool {
+    user_file(id).exists()
+}
+
+fn user_file(id: &str) -> PathBuf {
+    PathBuf::from(&data_dirs()[0]).join("applications").join(id)
+}
+
+/// The desktop file used for the ID, the user copy if there is one
+fn source_file(id: &str) -> Option<PathBuf> {
+    data_dirs()
+        .into_iter()
+        .map(|dir| PathBuf::from(dir).join("applications").join(id))
+        .find(|path| path.is_file())
+}
+
+/// Content of the desktop file used for the ID, the user copy if there is one
+pub fn read_entry(id: &str) -> io::Result<String> {
+    fs::read_to_string(source_file(id).ok_or(io::ErrorKind::NotFound)?)
+}
+
+fn write_user_file(id: &str, content: &str) -> io::Result<()> {
+    let path = user_file(id);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, content)
+}
+
+/// Value of a key in the [Desktop Entry] group. Localized keys like `Name[de]` are not matched
+pub fn get_key(content: &str, key: &str) -> Option<String> {
+    let mut in_entry = false;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_entry = line == "[Desktop Entry]";
+            continue;
+        }
+        if let Some((k, value)) = line.split_once('=')
+            && in_entry
+            && k.trim() == key
+        {
+            return Some(value.trim().to_string());
+        }
+    }
+    None
+}
+
+/// Sets a key in the [Desktop Entry] group, keeping every other line as it is.
+/// An empty value removes the key
+pub fn set_key(content: &str, key: &str, value: &str) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut in_entry = false;
+    // Where to add the key if it isn't there, after the last line of the group
+    let mut insert_at = None;
+    let mut found = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            in_entry = trimmed == "[Desktop Entry]";
+        } else if in_entry
+            && let Some((k, _)) = trimmed.split_once('=')
+            && k.trim() == key
+        {
+            found = true;
+            if !value.is_empty() {
+                lines.push(format!("{}={}", key, value));
+            }
+            continue;
+        }
+        lines.push(line.to_string());
+        if in_entry && !trimmed.is_empty() {
+            insert_at = Some(lines.len());
+        }
+    }
+    if !found && !value.is_empty() {
+        let line = format!("{}={}", key, value);
+        match insert_at {
+            Some(i) => lines.insert(i, line),
+            None => lines.insert(0, line),
+        }
+    }
+    let mut content = lines.join("\n");
+    content.push('\n');
+    content
+}
+
+/// File name for a new entry, from its name
+fn slug(name: &str) -> String {
+    let slug: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    slug.split('-').filter(|part| !part.is_empty()).collect::<Vec<&str>>().join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENTRY: &str = "[Desktop Entry]\nName=Foo\nExec=foo\n\n[Desktop Action new]\nName=New\nExec=foo --new\n";
+
+    #[test]
+    fn get_key_reads_the_entry_group() {
+        assert_eq!(get_key(ENTRY, "Name"), Some("Foo".to_string()));
+        assert_eq!(get_key(ENTRY, "Comment"), None);
+    }
+
+    #[test]
+    fn set_key_replaces_the_value() {
+        let content = set_key(ENTRY, "Exec", "foo %U");
+        assert_eq!(
+            content,
+            "[Desktop Entry]\nName=Foo\nExec=foo %U\n\n[Desktop Action new]\nName=New\nExec=foo --new\n"
+        );
+    }
+
+    #[test]
+    fn set_key_inserts_after_the_last_line_of_the_group() {
+        let content = set_key(ENTRY, "Comment", "A comment");
+        assert_eq!(
+            content,
+            "[Desktop Entry]\nName=Foo\nExec=foo\nComment=A comment\n\n[Desktop Action new]\nName=New\nExec=foo --new\n"
+        );
+    }
+
+    #[test]
+    fn set_key_removes_the_key_on_an_empty_value() {
+        let content = set_key(ENTRY, "Exec", "");
+        assert_eq!(content, "[Desktop Entry]\nName=Foo\n\n[Desktop Action new]\nName=New\nExec=foo --new\n");
+        assert_eq!(get_key(&content, "Exec"), None);
+    }
+
+    #[test]
+    fn slug_of_a_name_without_letters_is_empty() {
+        assert_eq!(slug("My App 2!"), "my-app-2");
+        assert_eq!(slug("!!!"), "");
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::SystemTime,
+};
+
+use crate::{
+    applications::{Action, Application},
+    config,
+    glob::wildcard_match,
+    mime,
+};
+
+/// Number of files sent to the UI at once while indexing
+const BATCH_SIZE: usize = 500;
+
+/// Walks the configured roots in a background thread, sending the files found in batches
+pub fn spawn_index(config: &config::Config) -> Receiver<Vec<Application>> {
+    let (sender, receiver) = mpsc::channel();
+    let files = config.files.clone();
+
+    thread::spawn(move || {
+        let home = std::env::var("HOME").unwrap_or_default();
+        let mut batch = Vec::new();
+        for root in &files.roots {
+            let root = expand_home(root, &home);
+            // Every directory is visited with its depth, starting at the root
+            let mut pending = vec![(root, 0)];
+            while let Some((dir, depth)) = pending.pop() {
+                let Ok(entries) = std::fs::read_dir(&dir) else {
+                    continue;
+                };
+                for entry in entries.flatten() {
+                    let file_name = entry.file_name().to_string_lossy().to_string();
+                    if !files.hidden && file_name.starts_with('.') {
+                        continue;
+                    }
+                    if files.ignore.iter().any(|p| wildcard_match(p, &file_name)) {
+                        continue;
+                    }
+                    // Symlinks are listed but not followed
+                    let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+                    if is_dir && depth < files.max_depth {
+                        pending.push((entry.path(), depth + 1));
+                    }
+
+                    batch.push(to_item(&entry.path(), is_dir, &home));
+                    if batch.len() >= BATCH_SIZE && sender.send(std::mem::take(&mut batch)).is_err() {
+                        // The launcher was closed
+                        return;
+                    }
+                }
+            }
+        }
+        if !batch.is_empty() {
+            let _ = sender.send(batch);
+        }
+    });
+
+    receiver
+}
+
+/// Icon theme name for the file type, like `image-png` or `folder`
+pub fn icon_name(path: &str) -> String {
+    if Path::new(path).is_dir() {
+        return "folder".to_string();
+    }
+    mime::mime_type(path).replace('/', "-")
+}
+
+/// Size, modification time and type of the file, for the Info pane
+pub fn metadata_lines(path: &str) -> Vec<String> {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return vec!["File not found".to_string()];
+    };
+    let mut lines = vec![format!("Type: {}", mime::mime_type(path))];
+    if metadata.is_file() {
+        lines.push(format!("Size: {}", human_size(metadata.len())));
+    }
+    if let Ok(modified) = metadata.modified() {
+        lines.push(format!("Modified: {}", time_ago(modified)));
+    }
+    lines
+}
+
+fn to_item(path: &Path, is_dir: bool, home: &str) -> Application {
+    let full_path = path.to_string_lossy().to_string();
+    let display_path = match full_path.strip_prefix(home) {
+        Some(rest) if !home.is_empty() => format!("~{}", rest),
+        _ => full_path.clone(),
+    };
+    let parent = path
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|| "/".to_string());
+
+    Application {
+        entry: full_path.clone(),
+        name: path
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_else(|| full_path.clone()),
+        comment: display_path.clone(),
+        categories: vec![if is_dir { "Directory" } else { "File" }.to_string()],
+        keywords: vec![display_path],
+        actions: vec![
+            Action {
+                name: "Open".to_string(),
+                command: full_path,
+            },
+            Action {
+                name: "Reveal in file manager".to_string(),
+                command: parent,
+            },
+        ],
+        ..Default::default()
+    }
+}
+
+fn expand_home(path: &str, home: &str) -> PathBuf {
+    match path.strip_prefix('~') {
+        Some(rest) => PathBuf::from(format!("{}{}", home, rest)),
+        None => PathBuf::from(path),
+    }
+}
+
+fn human_size(bytes: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, units[0])
+    } else {
+        format!("{:.1} {}", size, units[unit])
+    }
+}
+
+fn time_ago(time: SystemTime) -> String {
+    let Ok(elapsed) = SystemTime::now().duration_since(time) else {
+        return "just now".to_string();
+    };
+    let seconds = elapsed.as_secs();
+    match seconds {
+        0..60 => "just now".to_string(),
+        60..3600 => format!("{} minutes ago", seconds / 60),
+        3600..86400 => format!("{} hours ago", seconds / 3600),
+        86400..2592000 => format!("{} days ago", seconds / 86400),
+        2592000..31536000 => format!("{} months ago", seconds / 2592000),
+        _ => format!("{} years ago", seconds / 31536000),
+    }
+}
@@ -0,0 +1,48 @@
+/// Minimal glob matching with `*` and `?`
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn question_mark_matches_one_character() {
+        assert!(wildcard_match("file?.txt", "file1.txt"));
+        assert!(!wildcard_match("file?.txt", "file.txt"));
+        assert!(!wildcard_match("file?.txt", "file12.txt"));
+    }
+
+    #[test]
+    fn star_backtracks() {
+        assert!(wildcard_match("*.toml", "config.toml"));
+        assert!(wildcard_match("*.toml", ".toml"));
+        assert!(wildcard_match("a*b*c", "aXbYbZc"));
+        assert!(wildcard_match("*ab", "aab"));
+        assert!(wildcard_match("a*", "a"));
+        assert!(wildcard_match("**", ""));
+        assert!(!wildcard_match("*.toml", "config.toml.bak"));
+        assert!(!wildcard_match("a*b?c", "abc"));
+    }
+}
@@ -0,0 +1,119 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Write},
+    os::unix::{
+        fs::{DirBuilderExt, MetadataExt, PermissionsExt},
+        net::{UnixListener, UnixStream},
+    },
+    path::PathBuf,
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use clap::ValueEnum;
+use fs4::fs_std::FileExt;
+
+/// What a second launch does while the launcher is already open
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IfRunning {
+    /// Close the open launcher, so the same key binding opens and closes it
+    #[default]
+    Quit,
+    /// Clear the search of the open launcher, or set it to --query
+    Reset,
+    /// Fail with an error
+    Fail,
+}
+
+/// Message sent to the open launcher through the control socket
+pub enum Message {
+    Quit,
+    /// New search text
+    Query(String),
+}
+
+/// The lock and the control socket of the open launcher
+pub struct Instance {
+    // The lock is released when the file is closed
+    _lock: File,
+    socket: PathBuf,
+}
+
+impl Drop for Instance {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.socket);
+    }
+}
+
+/// Directory for the lock and the sockets, only readable by the user
+pub fn runtime_dir() -> PathBuf {
+    match std::env::var("XDG_RUNTIME_DIR") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir).join("termlaunch"),
+        _ => std::env::temp_dir().join(format!("termlaunch-{}", rustix::process::getuid().as_raw())),
+    }
+}
+
+/// Creates the runtime directory if needed. Fails if it isn't a directory only the user can use,
+/// since in the temporary directory another user could have created it first to take over the sockets
+pub fn create_runtime_dir() -> io::Result<PathBuf> {
+    let dir = runtime_dir();
+    fs::DirBuilder::new().recursive(true).mode(0o700).create(&dir)?;
+    let metadata = fs::symlink_metadata(&dir)?;
+    if !metadata.is_dir()
+        || metadata.uid() != rustix::process::getuid().as_raw()
+        || metadata.permissions().mode() & 0o777 != 0o700
+    {
+        return Err(io::Error::other(format!(
+            "{} must be a directory owned by you with mode 700, remove it or set XDG_RUNTIME_DIR",
+            dir.display()
+        )));
+    }
+    Ok(dir)
+}
+
+/// Takes the lock and listens on the control socket, returning the messages received on it.
+/// Returns `None` if another launcher has the lock
+pub fn acquire() -> io::Result<Option<(Instance, Receiver<Message>)>> {
+    let dir = create_runtime_dir()?;
+    let lock = File::create(dir.join("lock"))?;
+    if !lock.try_lock_exclusive()? {
+        return Ok(None);
+    }
+
+    // A socket left behind by a launcher that crashed can be replaced, since the lock is ours
+    let socket = dir.join("control.sock");
+    let _ = fs::remove_file(&socket);
+    let listener = UnixListener::bind(&socket)?;
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut line = String::new();
+            if BufReader::new(stream).read_line(&mut line).is_err() {
+                continue;
+            }
+            let line = line.trim_end_matches('\n');
+            let message = match line.strip_prefix("query ") {
+                Some(query) => Message::Query(query.to_string()),
+                None if line == "quit" => Message::Quit,
+                None => continue,
+            };
+            if sender.send(message).is_err() {
+                // The launcher was closed
+                return;
+            }
+        }
+    });
+
+    Ok(Some((Instance { _lock: lock, socket }, receiver)))
+}
+
+/// Tells the open launcher what to do, as set by `--if-running`
+pub fn notify(if_running: IfRunning, query: Option<String>) -> io::Result<()> {
+    let message = match if_running {
+        IfRunning::Quit => "quit\n".to_string(),
+        IfRunning::Reset => format!("query {}\n", query.unwrap_or_default().replace('\n', " ")),
+        IfRunning::Fail => return Err(io::Error::other("Termlaunch is already running")),
+    };
+    let socket = create_runtime_dir()?.join("control.sock");
+    UnixStream::connect(socket)?.write_all(message.as_bytes())
+}
@@ -1,31 +1,194 @@
-use clap::Parser;
-use fs4::fs_std::FileExt;
-use std::io::Result;
+use clap::{Parser, Subcommand};
+use color_eyre::Result;
 
 mod config;
+mod daemon;
 mod applications;
+mod autostart;
 mod app;
+mod cli;
 mod image;
+mod emoji;
+mod entries;
+mod instance;
+mod files;
+mod glob;
+mod matching;
+mod mime;
+mod mode;
+mod pins;
+mod session;
+mod ssh;
+mod watch;
 
 /// Open your desktop apps from the command line
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
     /// Alternative config file path. Defaults to $HOME/.config/termlaunch/config.toml or $XDG_CONFIG_HOME/termlaunch/config.toml
+    #[arg(short, long, global = true)]
+    config: Option<String>,
+    /// What to list and launch
+    #[arg(short, long, value_enum, default_value_t)]
+    mode: mode::Mode,
+    /// Print the command that would be run instead of running it
+    #[arg(
+        long,
+        value_enum,
+        global = true,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "shell"
+    )]
+    print: Option<cli::PrintFormat>,
+    /// Start with this text in the search box
     #[arg(short, long)]
-    config: Option<String>
+    query: Option<String>,
+    /// Only list entries in this category, like Game, Development or System
+    #[arg(long)]
+    category: Option<String>,
+    /// What to do if the launcher is already open
+    #[arg(long, value_enum, default_value_t)]
+    if_running: instance::IfRunning,
+    #[command(subcommand)]
+    command: Option<Commands>,
 }
 
-fn main() -> Result<()> {
-    let lock_file = "/tmp/termlaunch.lock";
-    let file = std::fs::File::create(lock_file)?;
-    let locked = file.try_lock_exclusive();
-    if !locked.unwrap() {
-        Err(std::io::Error::new(std::io::ErrorKind::Other, "Termlaunch is already running"))?;
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Open files or URLs, choosing between the applications that can handle them
+    Open {
+        /// Files or URLs to open
+        #[arg(required = true)]
+        targets: Vec<String>,
+        /// Open with the default application instead of showing the list
+        #[arg(short, long)]
+        default: bool,
+    },
+    /// Print the applications the launcher would show
+    List {
+        /// Print the full entries as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the applications matching a query, best matches first
+    Search {
+        query: String,
+        /// Print the full entries as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Launch an application by its desktop file ID, without opening the launcher
+    Launch {
+        /// Desktop file ID, like firefox.desktop. The .desktop suffix is optional
+        id: String,
+        /// Name of the action to run instead of the main command
+        #[arg(short, long)]
+        action: Option<String>,
+        /// Files or URLs passed to the application
+        files: Vec<String>,
+    },
+    /// Keep the applications in memory, so the launcher opens faster
+    Daemon,
+    /// Manage the config file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommands {
+    /// Check the config and its theme, printing every problem found. Fails if there are any
+    Check,
+    /// Write a config file with every key, its default and a comment explaining it
+    Init {
+        /// Replace the config file if it already exists
+        #[arg(short, long)]
+        force: bool,
+        /// Print the config instead of writing it
+        #[arg(long)]
+        stdout: bool,
+    },
+}
+
+/// Opens the launcher, or tells the one already open what to do
+fn start_launcher(options: app::Options, if_running: instance::IfRunning) -> Result<()> {
+    // Kept until the launcher is closed, to hold the lock
+    let Some((_instance, messages)) = instance::acquire()? else {
+        return Ok(instance::notify(if_running, options.query)?);
+    };
+    app::startup(app::Options {
+        control: Some(messages),
+        ..options
+    })
+}
+
+fn main() {
+    if let Err(e) = run(Args::parse()) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
     }
-    let args = Args::parse();
-    let _ = app::startup(args.config);
+}
 
-    Ok(())
+fn run(args: Args) -> Result<()> {
+    match args.command {
+        Some(Commands::Open { targets, default: true }) => {
+            mime::check_targets(&targets)?;
+            let config = cli::load_config(args.config);
+            cli::open_default(&targets, &config, args.print)?;
+        }
+        Some(Commands::Open { targets, .. }) => {
+            mime::check_targets(&targets)?;
+            start_launcher(
+                app::Options {
+                    config_path: args.config,
+                    mode: mode::Mode::Open,
+                    targets,
+                    print: args.print,
+                    query: args.query,
+                    category: args.category,
+                    ..Default::default()
+                },
+                args.if_running,
+            )?;
+        }
+        Some(Commands::Config { command: ConfigCommands::Check }) => {
+            cli::check_config(args.config)?;
+        }
+        Some(Commands::Config { command: ConfigCommands::Init { force, stdout } }) => {
+            cli::init_config(args.config, force, stdout)?;
+        }
+        Some(Commands::List { json }) => {
+            let config = cli::load_config(args.config);
+            cli::list(json, &config)?;
+        }
+        Some(Commands::Search { query, json }) => {
+            let config = cli::load_config(args.config);
+            cli::search_apps(&query, json, &config)?;
+        }
+        Some(Commands::Launch { id, action, files }) => {
+            mime::check_targets(&files)?;
+            let config = cli::load_config(args.config);
+            cli::launch(&id, action.as_deref(), &files, &config, args.print)?;
+        }
+        Some(Commands::Daemon) => {
+            daemon::run(args.config)?;
+        }
+        None => {
+            start_launcher(
+                app::Options {
+                    config_path: args.config,
+                    mode: args.mode,
+                    print: args.print,
+                    query: args.query,
+                    category: args.category,
+                    ..Default::default()
+                },
+                args.if_running,
+            )?;
+        }
+    }
 
+    Ok(())
 }
@@ -0,0 +1,30 @@
+/// Finds the query in the text, ignoring case, and returns the positions of the matched characters.
+/// A fuzzy match only needs the characters of the query to appear in order, otherwise they must be contiguous
+pub fn find(query: &str, text: &str, fuzzy: bool) -> Option<Vec<usize>> {
+    let query: Vec<char> = query.chars().map(lowercase).collect();
+    let text: Vec<char> = text.chars().map(lowercase).collect();
+
+    if query.is_empty() {
+        return Some(vec![]);
+    }
+
+    if fuzzy {
+        let mut positions = Vec::with_capacity(query.len());
+        let mut start = 0;
+        for q in &query {
+            let offset = text[start..].iter().position(|t| t == q)?;
+            positions.push(start + offset);
+            start += offset + 1;
+        }
+        return Some(positions);
+    }
+
+    text.windows(query.len())
+        .position(|window| window == query.as_slice())
+        .map(|start| (start..start + query.len()).collect())
+}
+
+/// Lowercases a single character, keeping the character count so positions stay valid
+fn lowercase(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
@@ -0,0 +1,275 @@
+use std::path::Path;
+
+use color_eyre::eyre::{Error, eyre};
+
+use crate::{
+    applications::{Action, Application},
+    config, daemon,
+    glob::wildcard_match,
+};
+
+/// Associations read from the mimeapps.list files, for a single MIME type
+#[derive(Default)]
+struct Associations {
+    defaults: Vec<String>,
+    added: Vec<String>,
+    removed: Vec<String>,
+}
+
+/// Guesses the MIME type of a file or URL.
+/// URLs use the `x-scheme-handler/<scheme>` type, files are matched against the shared MIME database globs
+pub fn mime_type(target: &str) -> String {
+    if let Some((scheme, _)) = target.split_once(':')
+        && scheme != "file"
+        && !scheme.is_empty()
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        && !Path::new(target).exists()
+    {
+        return format!("x-scheme-handler/{}", scheme.to_lowercase());
+    }
+
+    let path = file_url_path(target).unwrap_or_else(|| target.to_string());
+    let path = path.as_str();
+    if Path::new(path).is_dir() {
+        return "inode/directory".to_string();
+    }
+
+    let file_name = Path::new(path)
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    glob_mime_type(&file_name)
+        .or_else(|| mime_types_file(&file_name))
+        .unwrap_or_else(|| "application/octet-stream".to_string())
+}
+
+/// Path of a local `file://` URL, with its %XX escapes decoded.
+/// `None` for anything else, including files on another host
+pub fn file_url_path(target: &str) -> Option<String> {
+    let rest = target.strip_prefix("file://")?;
+    let path = match rest.find('/') {
+        Some(0) => rest,
+        Some(i) if rest[..i].eq_ignore_ascii_case("localhost") => &rest[i..],
+        _ => return None,
+    };
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = path.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    Some(String::from_utf8_lossy(&decoded).to_string())
+}
+
+/// Fails on `file://` URLs of another host, they can't be opened as local files
+pub fn check_targets(targets: &[String]) -> Result<(), Error> {
+    match targets
+        .iter()
+        .find(|target| target.starts_with("file://") && file_url_path(target).is_none())
+    {
+        Some(target) => Err(eyre!("{} is not a local file", target)),
+        None => Ok(()),
+    }
+}
+
+/// Applications that can open the target, with the default one first
+pub fn get_handlers(target: &str, config: &config::Config) -> Vec<Application> {
+    let mime = mime_type(target);
+    let associations = read_associations(&mime);
+    let apps = daemon::load_apps(true, config);
+
+    let mut ids: Vec<String> = Vec::new();
+    // Only the first installed default counts as the default handler
+    if let Some(default) = associations
+        .defaults
+        .iter()
+        .find(|id| apps.iter().any(|app| &app.entry == *id))
+    {
+        ids.push(default.clone());
+    }
+    for id in associations.added.iter().chain(
+        apps.iter()
+            .filter(|app| app.mime_types.contains(&mime))
+            .map(|app| &app.entry),
+    ) {
+        if !ids.contains(id) && !associations.removed.contains(id) {
+            ids.push(id.clone());
+        }
+    }
+
+    ids.iter()
+        .filter_map(|id| apps.iter().find(|app| &app.entry == id))
+        .enumerate()
+        .filter_map(|(i, app)| {
+            let run = app.actions.first()?;
+            Some(Application {
+                name: if i == 0 && associations.defaults.contains(&app.entry) {
+                    format!("{} (default)", app.name)
+                } else {
+                    app.name.clone()
+                },
+                comment: format!("Open as {}", mime),
+                actions: vec![Action {
+                    name: "Open".to_string(),
+                    command: run.command.clone(),
+                }],
+                ..app.clone()
+            })
+        })
+        .collect()
+}
+
+/// The application that opens the target by default, or the first one that can open it
+pub fn default_handler(target: &str, config: &config::Config) -> Result<Application, Error> {
+    get_handlers(target, config)
+        .into_iter()
+        .next()
+        .ok_or_else(|| eyre!("No application can open {} ({})", target, mime_type(target)))
+}
+
+/// The XDG data directories, the user's one first
+pub fn data_dirs() -> Vec<String> {
+    let data_home = std::env::var("XDG_DATA_HOME")
+        .unwrap_or_else(|_| format!("{}/.local/share", std::env::var("HOME").unwrap_or_default()));
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .unwrap_or_else(|_| "/usr/local/share:/usr/share".to_string());
+    std::iter::once(data_home)
+        .chain(data_dirs.split(':').map(|d| d.to_string()))
+        .collect()
+}
+
+/// The XDG config directories, the user's one first
+pub fn config_dirs() -> Vec<String> {
+    let config_home = std::env::var("XDG_CONFIG_HOME")
+        .unwrap_or_else(|_| format!("{}/.config", std::env::var("HOME").unwrap_or_default()));
+    let config_dirs = std::env::var("XDG_CONFIG_DIRS").unwrap_or_else(|_| "/etc/xdg".to_string());
+    std::iter::once(config_home)
+        .chain(config_dirs.split(':').map(|d| d.to_string()))
+        .collect()
+}
+
+/// Looks up the file name in the `mime/globs2` files, the highest weight and then the longest pattern wins
+fn glob_mime_type(file_name: &str) -> Option<String> {
+    let mut best: Option<(u32, usize, String)> = None;
+    for dir in data_dirs() {
+        let Ok(content) = std::fs::read_to_string(format!("{}/mime/globs2", dir)) else {
+            continue;
+        };
+        for line in content.lines() {
+            if line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split(':');
+            let (Some(weight), Some(mime), Some(pattern)) = (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let case_sensitive = fields.next().is_some_and(|flags| flags.contains("cs"));
+            let matches = if case_sensitive {
+                wildcard_match(pattern, file_name)
+            } else {
+                wildcard_match(&pattern.to_lowercase(), &file_name.to_lowercase())
+            };
+            if !matches {
+                continue;
+            }
+            let weight: u32 = weight.parse().unwrap_or(50);
+            let is_better = best
+                .as_ref()
+                .is_none_or(|(w, len, _)| (weight, pattern.len()) > (*w, *len));
+            if is_better {
+                best = Some((weight, pattern.len(), mime.to_string()));
+            }
+        }
+    }
+    best.map(|(_, _, mime)| mime)
+}
+
+/// Fallback for systems without shared-mime-info, using the extension list in /etc/mime.types
+fn mime_types_file(file_name: &str) -> Option<String> {
+    let (_, extension) = file_name.rsplit_once('.')?;
+    let extension = extension.to_lowercase();
+    let content = std::fs::read_to_string("/etc/mime.types").ok()?;
+    content
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .find_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mime = fields.next()?;
+            fields.any(|ext| ext == extension).then(|| mime.to_string())
+        })
+}
+
+/// Reads the mimeapps.list files in order of precedence, as described by the XDG MIME applications spec
+fn read_associations(mime: &str) -> Associations {
+    let files = config_dirs()
+        .into_iter()
+        .map(|dir| format!("{}/mimeapps.list", dir))
+        .chain(
+            data_dirs()
+                .into_iter()
+                .map(|dir| format!("{}/applications/mimeapps.list", dir)),
+        );
+
+    let mut associations = Associations::default();
+    for file in files {
+        let Ok(content) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        let mut section = String::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.starts_with('[') {
+                section = line.to_string();
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            if key.trim() != mime {
+                continue;
+            }
+            let ids = value
+                .split(';')
+                .map(|id| id.trim().to_string())
+                .filter(|id| !id.is_empty());
+            let list = match section.as_str() {
+                "[Default Applications]" => &mut associations.defaults,
+                "[Added Associations]" => &mut associations.added,
+                "[Removed Associations]" => &mut associations.removed,
+                _ => continue,
+            };
+            for id in ids {
+                // Files read earlier have precedence
+                if !list.contains(&id) {
+                    list.push(id);
+                }
+            }
+        }
+    }
+    associations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_url_path_decodes_local_urls() {
+        assert_eq!(file_url_path("file:///home/u/My%20File.pdf"), Some("/home/u/My File.pdf".to_string()));
+        assert_eq!(file_url_path("file://localhost/a%2Fb%zz"), Some("/a/b%zz".to_string()));
+        assert_eq!(file_url_path("file://host/a"), None);
+        assert_eq!(file_url_path("/home/u/a.pdf"), None);
+    }
+}
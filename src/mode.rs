@@ -0,0 +1,72 @@
+use std::sync::mpsc::Receiver;
+
+use clap::ValueEnum;
+
+use crate::{
+    applications::Application,
+    autostart,
+    config::Config,
+    daemon,
+    emoji, files, mime, session, ssh,
+};
+
+/// What the launcher lists and what happens when an entry is picked
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Desktop applications
+    #[default]
+    Apps,
+    /// Emoji and Unicode symbols
+    Emoji,
+    /// SSH hosts from ~/.ssh/config and known_hosts
+    Ssh,
+    /// Lock, log out, suspend, hibernate, reboot and shut down
+    Session,
+    /// Files below the directories set in `[files]`
+    Files,
+    /// Applications started with the session, to enable or disable them
+    Autostart,
+    /// Applications that can open the given files or URLs, used by `termlaunch open`
+    #[value(skip)]
+    Open,
+}
+
+impl Mode {
+    /// Entries shown in the list for this mode
+    pub fn items(&self, config: &Config, targets: &[String]) -> Vec<Application> {
+        match self {
+            Mode::Apps => daemon::load_apps(false, config),
+            Mode::Emoji => emoji::get_chars(),
+            Mode::Ssh => ssh::get_hosts(),
+            Mode::Session => session::get_entries(config),
+            // Files are indexed in the background, see `index`
+            Mode::Files => vec![],
+            Mode::Autostart => autostart::get_entries(),
+            Mode::Open => targets
+                .first()
+                .map(|target| mime::get_handlers(target, config))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Title of the list block
+    pub fn title(&self) -> &'static str {
+        match self {
+            Mode::Apps => "Applications",
+            Mode::Emoji => "Characters",
+            Mode::Ssh => "Hosts",
+            Mode::Session => "Session",
+            Mode::Files => "Files",
+            Mode::Autostart => "Autostart",
+            Mode::Open => "Open with",
+        }
+    }
+
+    /// Entries that keep arriving after startup, for modes that are slow to list
+    pub fn index(&self, config: &Config) -> Option<Receiver<Vec<Application>>> {
+        match self {
+            Mode::Files => Some(files::spawn_index(config)),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::{fs, io, path::PathBuf};
+
+use crate::config;
+
+/// File where the pins toggled from the launcher are kept.
+/// One desktop file ID per line, a line starting with `!` unpins an app pinned in the config
+fn state_file() -> PathBuf {
+    let state_home = std::env::var("XDG_STATE_HOME")
+        .unwrap_or_else(|_| format!("{}/.local/state", std::env::var("HOME").unwrap_or_default()));
+    PathBuf::from(state_home).join("termlaunch").join("pinned")
+}
+
+fn read_state() -> Vec<String> {
+    fs::read_to_string(state_file())
+        .map(|content| {
+            content
+                .lines()
+                .map(|line| line.trim().to_string())
+                .filter(|line| !line.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Whether two IDs are the same app. The .desktop suffix is optional, like for `launch`,
+/// and custom entries from the config have none
+fn same_id(a: &str, b: &str) -> bool {
+    a.strip_suffix(".desktop").unwrap_or(a) == b.strip_suffix(".desktop").unwrap_or(b)
+}
+
+/// Whether the app with this ID is in the list returned by `load`
+pub fn contains(pinned: &[String], id: &str) -> bool {
+    pinned.iter().any(|p| same_id(p, id))
+}
+
+/// IDs of the pinned apps, from the config and the state file
+pub fn load(config: &config::Config) -> Vec<String> {
+    let state = read_state();
+    let unpinned: Vec<&str> = state.iter().filter_map(|line| line.strip_prefix('!')).collect();
+    let mut pinned: Vec<String> = vec![];
+    for id in &config.pinned {
+        if !unpinned.iter().any(|u| same_id(u, id)) && !contains(&pinned, id) {
+            pinned.push(id.clone());
+        }
+    }
+    for id in &state {
+        if !id.starts_with('!') && !contains(&pinned, id) {
+            pinned.push(id.clone());
+        }
+    }
+    pinned
+}
+
+/// Pins or unpins an app and saves it to the state file. Returns whether it is pinned now
+pub fn toggle(id: &str, config: &config::Config) -> io::Result<bool> {
+    let mut state = read_state();
+    let pinned = contains(&load(config), id);
+    state.retain(|line| !same_id(line.strip_prefix('!').unwrap_or(line), id));
+    if pinned && contains(&config.pinned, id) {
+        state.push(format!("!{}", id));
+    } else if !pinned {
+        state.push(id.to_string());
+    }
+
+    let path = state_file();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut content = state.join("\n");
+    content.push('\n');
+    fs::write(path, content)?;
+    Ok(!pinned)
+}
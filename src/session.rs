@@ -0,0 +1,38 @@
+use crate::{
+    applications::{Action, Application},
+    config,
+};
+
+/// Entry IDs of the session actions that end the session or power off the machine
+const DESTRUCTIVE: [&str; 3] = ["logout", "reboot", "shutdown"];
+
+pub fn get_entries(config: &config::Config) -> Vec<Application> {
+    let session = &config.session;
+    [
+        ("lock", "Lock", "Lock the screen", "system-lock-screen", &session.lock),
+        ("logout", "Log out", "End the current session", "system-log-out", &session.logout),
+        ("suspend", "Suspend", "Suspend to RAM", "system-suspend", &session.suspend),
+        ("hibernate", "Hibernate", "Suspend to disk", "system-suspend-hibernate", &session.hibernate),
+        ("reboot", "Reboot", "Restart the computer", "system-reboot", &session.reboot),
+        ("shutdown", "Shut down", "Power off the computer", "system-shutdown", &session.shutdown),
+    ]
+    .into_iter()
+    .map(|(entry, name, comment, icon, command)| Application {
+        entry: entry.to_string(),
+        name: name.to_string(),
+        comment: comment.to_string(),
+        icon: icon.to_string(),
+        categories: vec!["System".to_string()],
+        actions: vec![Action {
+            name: name.to_string(),
+            command: command.clone(),
+        }],
+        ..Default::default()
+    })
+    .collect()
+}
+
+/// Whether running this entry should be confirmed first
+pub fn needs_confirmation(entry: &str, config: &config::Config) -> bool {
+    config.session.confirm && DESTRUCTIVE.contains(&entry)
+}
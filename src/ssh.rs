@@ -0,0 +1,182 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    applications::{Action, Application},
+    glob::wildcard_match,
+};
+
+/// Same limit OpenSSH uses for nested Include directives
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+pub fn get_hosts() -> Vec<Application> {
+    let Ok(home) = std::env::var("HOME") else {
+        return vec![];
+    };
+    let ssh_dir = PathBuf::from(home).join(".ssh");
+
+    let mut hosts: Vec<Application> = Vec::new();
+    read_config(&ssh_dir.join("config"), &ssh_dir, 0, &mut hosts);
+    read_known_hosts(&ssh_dir.join("known_hosts"), &mut hosts);
+
+    hosts.sort_by_key(|h| h.name.to_lowercase());
+    hosts
+}
+
+fn read_config(path: &Path, ssh_dir: &Path, depth: usize, hosts: &mut Vec<Application>) {
+    if depth > MAX_INCLUDE_DEPTH {
+        return;
+    }
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return;
+    };
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // Keywords are case insensitive and can be separated from the arguments by spaces or '='
+        let (keyword, args) = line
+            .split_once(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or((line, ""));
+        let args = args.trim_start_matches(|c: char| c.is_whitespace() || c == '=');
+
+        if keyword.eq_ignore_ascii_case("Host") {
+            for host in args.split_whitespace() {
+                // Patterns and negations are not real hosts we can connect to
+                if host.contains(['*', '?', '!']) {
+                    continue;
+                }
+                add_host(host, "From ~/.ssh/config", hosts);
+            }
+        } else if keyword.eq_ignore_ascii_case("Include") {
+            for include in args.split_whitespace() {
+                for file in expand_include(include, ssh_dir) {
+                    read_config(&file, ssh_dir, depth + 1, hosts);
+                }
+            }
+        }
+    }
+}
+
+fn read_known_hosts(path: &Path, hosts: &mut Vec<Application>) {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return;
+    };
+
+    for line in content.lines() {
+        let line = line.trim();
+        // Skip comments and @cert-authority/@revoked markers
+        if line.is_empty() || line.starts_with('#') || line.starts_with('@') {
+            continue;
+        }
+        let Some(names) = line.split_whitespace().next() else {
+            continue;
+        };
+        // Hashed entries (|1|salt|hash) can't be turned back into a host name
+        if names.starts_with('|') {
+            continue;
+        }
+        for name in names.split(',') {
+            if name.contains(['*', '?', '!']) {
+                continue;
+            }
+            add_host(&destination(name), "From ~/.ssh/known_hosts", hosts);
+        }
+    }
+}
+
+/// Turns the `[host]:port` form used in known_hosts into something ssh accepts
+fn destination(name: &str) -> String {
+    if let Some(rest) = name.strip_prefix('[')
+        && let Some((host, port)) = rest.split_once("]:")
+    {
+        if port == "22" {
+            return host.to_string();
+        }
+        return format!("ssh://{}:{}", host, port);
+    }
+    name.to_string()
+}
+
+fn add_host(host: &str, comment: &str, hosts: &mut Vec<Application>) {
+    // A host from the config wins over the same one in known_hosts
+    if hosts.iter().any(|h| h.entry == host) {
+        return;
+    }
+    hosts.push(Application {
+        entry: host.to_string(),
+        name: host.to_string(),
+        comment: comment.to_string(),
+        icon: "utilities-terminal".to_string(),
+        terminal: true,
+        categories: vec!["Network".to_string(), "RemoteAccess".to_string()],
+        actions: vec![Action {
+            name: "Connect".to_string(),
+            command: format!("ssh {}", host),
+        }],
+        ..Default::default()
+    });
+}
+
+/// Resolves an Include argument into the files it refers to.
+/// Relative paths are relative to ~/.ssh and the file name may contain glob wildcards
+fn expand_include(include: &str, ssh_dir: &Path) -> Vec<PathBuf> {
+    let path = if let Some(rest) = include.strip_prefix("~/") {
+        ssh_dir.parent().unwrap_or(ssh_dir).join(rest)
+    } else {
+        ssh_dir.join(include)
+    };
+
+    let file_name = path.file_name().and_then(|f| f.to_str()).unwrap_or_default();
+    if !file_name.contains(['*', '?']) {
+        return vec![path];
+    }
+
+    let Some(dir) = path.parent() else {
+        return vec![];
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|e| wildcard_match(file_name, &e.file_name().to_string_lossy()))
+        .map(|e| e.path())
+        .collect();
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn destination_keeps_the_port() {
+        assert_eq!(destination("example.com"), "example.com");
+        assert_eq!(destination("[example.com]:2222"), "ssh://example.com:2222");
+        assert_eq!(destination("[10.0.0.1]:22"), "10.0.0.1");
+        assert_eq!(destination("[::1]:2200"), "ssh://::1:2200");
+    }
+
+    #[test]
+    fn expand_include_resolves_paths() {
+        let ssh_dir = Path::new("/home/u/.ssh");
+        assert_eq!(expand_include("config.d/work", ssh_dir), vec![PathBuf::from("/home/u/.ssh/config.d/work")]);
+        assert_eq!(expand_include("~/other", ssh_dir), vec![PathBuf::from("/home/u/other")]);
+        assert_eq!(expand_include("/etc/ssh/extra", ssh_dir), vec![PathBuf::from("/etc/ssh/extra")]);
+    }
+
+    #[test]
+    fn expand_include_matches_wildcards() {
+        let ssh_dir = std::env::temp_dir().join(format!("termlaunch-ssh-test-{}", std::process::id()));
+        std::fs::create_dir_all(ssh_dir.join("config.d")).unwrap();
+        for name in ["b.conf", "a.conf", "notes.txt"] {
+            std::fs::write(ssh_dir.join("config.d").join(name), "").unwrap();
+        }
+        let files = expand_include("config.d/*.conf", &ssh_dir);
+        std::fs::remove_dir_all(&ssh_dir).unwrap();
+        assert_eq!(files, vec![ssh_dir.join("config.d/a.conf"), ssh_dir.join("config.d/b.conf")]);
+    }
+}
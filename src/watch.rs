@@ -0,0 +1,134 @@
+use std::{
+    ffi::OsStr,
+    mem::MaybeUninit,
+    os::{fd::OwnedFd, unix::ffi::OsStrExt},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+use rustix::{
+    fs::inotify::{self, CreateFlags, ReadFlags, WatchFlags},
+    io::Errno,
+};
+
+use crate::glob::wildcard_match;
+
+/// A watched directory and the pattern its files are matched against
+struct Watch {
+    wd: i32,
+    dir: PathBuf,
+    pattern: String,
+}
+
+fn watch_flags() -> WatchFlags {
+    WatchFlags::CLOSE_WRITE | WatchFlags::MOVED_TO | WatchFlags::DELETE | WatchFlags::MOVED_FROM | WatchFlags::CREATE
+}
+
+/// Watches the directory. If it doesn't exist yet, its nearest existing parent is watched instead
+/// and the watch is added to `pending`, with the parent's descriptor, until the directory is created
+fn watch_dir(fd: &OwnedFd, dir: PathBuf, pattern: String, watched: &mut Vec<Watch>, pending: &mut Vec<Watch>) -> bool {
+    if let Ok(wd) = inotify::add_watch(fd, &dir, watch_flags()) {
+        watched.push(Watch { wd, dir, pattern });
+        return true;
+    }
+    if let Some(wd) = dir
+        .ancestors()
+        .skip(1)
+        .find_map(|parent| inotify::add_watch(fd, parent, watch_flags()).ok())
+    {
+        pending.push(Watch { wd, dir, pattern });
+    }
+    false
+}
+
+/// Watches the directories waited for below the directory of the event, sending the matching files
+/// already in the ones that were created. Returns false if the launcher was closed
+fn add_pending(
+    fd: &OwnedFd,
+    wd: i32,
+    watched: &mut Vec<Watch>,
+    pending: &mut Vec<Watch>,
+    sender: &Sender<PathBuf>,
+) -> bool {
+    let (ready, waiting): (Vec<Watch>, Vec<Watch>) = std::mem::take(pending).into_iter().partition(|w| w.wd == wd);
+    *pending = waiting;
+    for Watch { dir, pattern, .. } in ready {
+        if !watch_dir(fd, dir.clone(), pattern.clone(), watched, pending) {
+            continue;
+        }
+        // Files can be written before the watch is added, like with `mkdir -p` and `cp` in a row
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if wildcard_match(&pattern, &entry.file_name().to_string_lossy()) && sender.send(entry.path()).is_err() {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Watches files with inotify in a background thread, sending the path of a file every time it is saved or removed.
+/// The file names can be patterns like `*.toml`. Their directories are watched instead of the files,
+/// so editors that save by replacing the file are noticed too. Directories that don't exist yet are
+/// watched once they are created
+pub fn spawn_watcher(files: Vec<PathBuf>) -> Option<Receiver<PathBuf>> {
+    let fd = inotify::init(CreateFlags::CLOEXEC).ok()?;
+    let mut watched = Vec::new();
+    let mut pending = Vec::new();
+    for file in &files {
+        let (Some(dir), Some(name)) = (file.parent(), file.file_name()) else {
+            continue;
+        };
+        // The parent of a file name alone is empty
+        let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+        watch_dir(&fd, dir.to_path_buf(), name.to_string_lossy().to_string(), &mut watched, &mut pending);
+    }
+    if watched.is_empty() && pending.is_empty() {
+        return None;
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buffer = [MaybeUninit::uninit(); 4096];
+        let mut reader = inotify::Reader::new(&fd, &mut buffer);
+        loop {
+            let (wd, flags, name) = match reader.next() {
+                Ok(event) => (
+                    event.wd(),
+                    event.events(),
+                    event
+                        .file_name()
+                        .map(|name| OsStr::from_bytes(name.to_bytes()).to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                ),
+                Err(Errno::INTR) => continue,
+                Err(_) => return,
+            };
+            if flags.contains(ReadFlags::ISDIR)
+                && (flags.contains(ReadFlags::CREATE) || flags.contains(ReadFlags::MOVED_TO))
+                && !add_pending(&fd, wd, &mut watched, &mut pending, &sender)
+            {
+                return;
+            }
+            // A created file is empty, it is sent once it is written
+            if flags.contains(ReadFlags::CREATE) {
+                continue;
+            }
+            let changed = watched
+                .iter()
+                .find(|w| w.wd == wd && wildcard_match(&w.pattern, &name))
+                .map(|w| w.dir.join(&name));
+            if let Some(path) = changed
+                && sender.send(path).is_err()
+            {
+                // The launcher was closed
+                return;
+            }
+        }
+    });
+
+    Some(receiver)
+}